@@ -15,7 +15,10 @@ use std::convert::identity;
 
 use super::config::{APP_ID, PROFILE};
 use actions::{setup_accels, setup_actions};
-use components::{about_dialog, add_directory_dialog, add_feed_dialog, content, help_overlay};
+use components::{
+    about_dialog, add_directory_dialog, add_feed_dialog, add_filter_dialog, content, help_overlay,
+    tags_dialog,
+};
 
 /// Message broker
 pub static BROKER: MessageBroker<Model> = MessageBroker::new();
@@ -40,6 +43,12 @@ pub struct Model {
     /// Add Directory Dialog
     #[allow(dead_code)]
     add_directory_dialog: Controller<add_directory_dialog::Model>,
+    /// Add Filter Dialog
+    #[allow(dead_code)]
+    add_filter_dialog: Controller<add_filter_dialog::Model>,
+    /// Tags Dialog
+    #[allow(dead_code)]
+    tags_dialog: Controller<tags_dialog::Model>,
 }
 
 /// Settings
@@ -113,6 +122,12 @@ impl SimpleComponent for Model {
         let add_directory_dialog = add_directory_dialog::Model::builder()
             .launch_with_broker((), &add_directory_dialog::BROKER)
             .forward(sender.input_sender(), identity);
+        let add_filter_dialog = add_filter_dialog::Model::builder()
+            .launch_with_broker((), &add_filter_dialog::BROKER)
+            .forward(sender.input_sender(), identity);
+        let tags_dialog = tags_dialog::Model::builder()
+            .launch_with_broker((), &tags_dialog::BROKER)
+            .forward(sender.input_sender(), identity);
         // Initialize the model
         let model = Self {
             settings: gio::Settings::new(APP_ID),
@@ -122,6 +137,8 @@ impl SimpleComponent for Model {
             help_overlay,
             add_feed_dialog,
             add_directory_dialog,
+            add_filter_dialog,
+            tags_dialog,
         };
         // Set the components as transient to the root
         model.about_dialog.widget().set_transient_for(Some(root));
@@ -131,9 +148,14 @@ impl SimpleComponent for Model {
             .add_directory_dialog
             .widget()
             .set_transient_for(Some(root));
+        model
+            .add_filter_dialog
+            .widget()
+            .set_transient_for(Some(root));
+        model.tags_dialog.widget().set_transient_for(Some(root));
         let widgets = view_output!();
         // Setup actions
-        setup_actions(&widgets.app_window);
+        setup_actions(&widgets.app_window, &model.settings);
         // Add a CSS style to the window if it's a development build
         if PROFILE == "dev" {
             widgets.app_window.add_css_class("devel");
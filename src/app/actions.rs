@@ -1,9 +1,10 @@
 //! Actions
 
-use gtk::prelude::{GtkApplicationExt, WidgetExt};
+use gtk::gio;
+use gtk::prelude::{GtkApplicationExt, SettingsExt, WidgetExt};
 
 use super::components::{
-    about_dialog, add_directory_dialog, add_feed_dialog, content, help_overlay,
+    about_dialog, add_directory_dialog, add_feed_dialog, add_filter_dialog, content, help_overlay,
 };
 use super::Msg;
 use relm4::actions::{AccelsPlus, RelmAction, RelmActionGroup};
@@ -12,14 +13,19 @@ relm4::new_action_group!(pub(super) WindowActionGroup, "win");
 relm4::new_stateless_action!(pub(super) ShowHelpOverlay, WindowActionGroup, "show-help-overlay");
 relm4::new_stateless_action!(pub(super) ShowAddFeedDialog, WindowActionGroup, "show-add-feed-dialog");
 relm4::new_stateless_action!(pub(super) ShowAddDirectoryDialog, WindowActionGroup, "show-add-directory-dialog");
+relm4::new_stateless_action!(pub(super) ShowAddFilterDialog, WindowActionGroup, "show-add-filter-dialog");
 relm4::new_stateless_action!(pub(super) UpdateAllFeeds, WindowActionGroup, "update-all-feeds");
+relm4::new_stateless_action!(pub(super) UpdateCurrentSource, WindowActionGroup, "update-current-source");
+relm4::new_stateless_action!(pub(super) SearchTidings, WindowActionGroup, "search-tidings");
+relm4::new_stateful_action!(pub(super) RefreshInterval, WindowActionGroup, "refresh-interval", u32, u32);
+relm4::new_stateful_action!(pub(super) RetentionPolicy, WindowActionGroup, "retention-policy", String, String);
 
 relm4::new_action_group!(pub(super) ApplicationActionGroup, "app");
 relm4::new_stateless_action!(pub(super) ShowAboutDialog, ApplicationActionGroup, "about");
 relm4::new_stateless_action!(pub(super) QuitApplication, ApplicationActionGroup, "quit");
 
 /// Setup actions for the application window
-pub(super) fn setup_actions(app_window: &adw::ApplicationWindow) {
+pub(super) fn setup_actions(app_window: &adw::ApplicationWindow, settings: &gio::Settings) {
     // Prepare action groups
     let window_actions = RelmActionGroup::<WindowActionGroup>::new();
     let application_actions = RelmActionGroup::<ApplicationActionGroup>::new();
@@ -36,12 +42,53 @@ pub(super) fn setup_actions(app_window: &adw::ApplicationWindow) {
                 add_directory_dialog::BROKER.send(add_directory_dialog::Msg::Show);
             }
         });
+    // Create the Show Add Filter Dialog action
+    let show_add_filter_dialog_action: RelmAction<ShowAddFilterDialog> =
+        RelmAction::new_stateless({
+            move |_| {
+                add_filter_dialog::BROKER.send(add_filter_dialog::Msg::Show);
+            }
+        });
     // Create the Update All Feeds action
     let update_all_feeds_action: RelmAction<UpdateAllFeeds> = RelmAction::new_stateless({
         move |_| {
             content::BROKER.send(content::Msg::ToggleUpdateAll);
         }
     });
+    // Create the Update Current Source action
+    let update_current_source_action: RelmAction<UpdateCurrentSource> =
+        RelmAction::new_stateless({
+            move |_| {
+                content::BROKER.send(content::Msg::StartUpdateCurrent);
+            }
+        });
+    // Create the Search Tidings action
+    let search_tidings_action: RelmAction<SearchTidings> = RelmAction::new_stateless({
+        move |_| {
+            content::BROKER.send(content::Msg::ToggleSearch);
+        }
+    });
+    // Create the Refresh Interval action
+    let refresh_interval_action: RelmAction<RefreshInterval> =
+        RelmAction::new_stateful_with_target_value(&settings.uint("refresh-interval"), {
+            let settings = settings.clone();
+            move |_, state, interval| {
+                // Save the interval (the content reschedules the update on change)
+                *state = interval;
+                settings.set_uint("refresh-interval", interval).ok();
+            }
+        });
+    // Create the Retention Policy action
+    let retention: String = settings.string("retention").into();
+    let retention_policy_action: RelmAction<RetentionPolicy> =
+        RelmAction::new_stateful_with_target_value(&retention, {
+            let settings = settings.clone();
+            move |_, state, policy| {
+                // Save the policy (the content prunes the stored tidings on change)
+                settings.set_string("retention", &policy).ok();
+                *state = policy;
+            }
+        });
     // Create the Show Help Overlay action
     let show_help_overlay_action: RelmAction<ShowHelpOverlay> = RelmAction::new_stateless({
         move |_| {
@@ -64,7 +111,12 @@ pub(super) fn setup_actions(app_window: &adw::ApplicationWindow) {
     window_actions.add_action(show_help_overlay_action);
     window_actions.add_action(show_add_feed_dialog_action);
     window_actions.add_action(show_add_directory_dialog_action);
+    window_actions.add_action(show_add_filter_dialog_action);
     window_actions.add_action(update_all_feeds_action);
+    window_actions.add_action(update_current_source_action);
+    window_actions.add_action(search_tidings_action);
+    window_actions.add_action(refresh_interval_action);
+    window_actions.add_action(retention_policy_action);
     application_actions.add_action(show_about_dialog_action);
     application_actions.add_action(quit_application_action);
     // Insert the action groups into the window
@@ -81,4 +133,6 @@ pub fn setup_accels(app: &impl GtkApplicationExt) {
     app.set_accelerators_for_action::<ShowAddFeedDialog>(&["<primary>a"]);
     app.set_accelerators_for_action::<ShowAddDirectoryDialog>(&["<primary>d"]);
     app.set_accelerators_for_action::<UpdateAllFeeds>(&["<primary>r"]);
+    app.set_accelerators_for_action::<UpdateCurrentSource>(&["<primary><shift>r"]);
+    app.set_accelerators_for_action::<SearchTidings>(&["<primary>f"]);
 }
@@ -5,5 +5,7 @@ use super::Msg as AppMsg;
 pub mod about_dialog;
 pub mod add_directory_dialog;
 pub mod add_feed_dialog;
+pub mod add_filter_dialog;
 pub mod content;
 pub mod help_overlay;
+pub mod tags_dialog;
@@ -1,14 +1,20 @@
 //! Add Feed Dialog
 
+mod candidate;
+mod discover;
+mod normalize;
+
 use adw::prelude::{ActionRowExt, PreferencesRowExt};
 use gtk::prelude::{
-    BoxExt, ButtonExt, EditableExt, EntryBufferExtManual, EntryExt, GtkWindowExt, OrientableExt,
-    WidgetExt,
+    BoxExt, ButtonExt, EditableExt, EntryBufferExtManual, EntryExt, GtkWindowExt, ListBoxRowExt,
+    OrientableExt, WidgetExt,
 };
+use relm4::factory::FactoryVecDeque;
 use relm4::{ComponentParts, ComponentSender, MessageBroker, SimpleComponent};
 
 use super::content;
 use super::AppMsg;
+use candidate::Model as Candidate;
 
 /// Message broker
 pub static BROKER: MessageBroker<Model> = MessageBroker::new();
@@ -23,8 +29,34 @@ pub struct Model {
     url: gtk::EntryBuffer,
     /// Is the feed allowed to be added?
     allowed: bool,
-    /// Should we go to the next page?
-    next_page: bool,
+    /// Page we should be on
+    page: Page,
+    /// Are we looking for the feeds at the URL?
+    discovering: bool,
+    /// Error of the URL (if there is one)
+    error: Option<String>,
+    /// Feeds found at the URL
+    candidates: FactoryVecDeque<Candidate>,
+    /// Are we fetching the feed for a preview?
+    previewing: bool,
+    /// Preview of the latest tidings of the feed
+    preview: String,
+    /// Title and URL of the feed with the same URL that is already subscribed
+    duplicate: Option<(String, String)>,
+    /// Generation of the requests in the background (the
+    /// results of the requests of the older ones are ignored)
+    generation: u64,
+}
+
+/// Pages of the dialog
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Page {
+    /// Enter the URL
+    Url,
+    /// Choose one of the feeds found at the URL
+    Feeds,
+    /// Enter the title
+    Title,
 }
 
 /// Messages
@@ -36,12 +68,83 @@ pub enum Msg {
     Hide,
     /// Check if the feed is allowed to be added
     Check,
-    /// Go to the next page
+    /// Look for the feeds at the URL
     Next,
+    /// The feeds have been looked for (the request of this generation might have failed)
+    Discovered(u64, Result<Vec<Candidate>, reqwest::Error>),
+    /// Choose the feed with this index in the list of candidates
+    Select(i32),
+    /// The feed has been fetched for a preview (the request of this generation might have failed)
+    Previewed(u64, Result<Option<Candidate>, reqwest::Error>),
+    /// A feed with the same URL is already subscribed (title, URL)
+    Duplicate(String, String),
+    /// Show the already subscribed feed instead of adding a new one
+    GoToDuplicate,
     /// Add the feed
     Add,
 }
 
+impl Model {
+    /// Start a new generation of the requests in the background, returning its number
+    fn next_generation(&mut self) -> u64 {
+        self.generation = self.generation.wrapping_add(1);
+        self.generation
+    }
+    /// Use the URL of the candidate and go to the title page, previewing the feed
+    /// (it's fetched in the background, unless that has been done already)
+    fn show_title_page(&mut self, candidate: Candidate, sender: &ComponentSender<Self>) {
+        // Use the URL of the feed
+        self.url.set_text(&candidate.url);
+        self.page = Page::Title;
+        // Check if it's already subscribed
+        self.duplicate = None;
+        content::BROKER.send(content::Msg::FindDuplicate(candidate.url.clone()));
+        // If the feed has been fetched already
+        if candidate.items.is_some() {
+            // Show the preview right away
+            self.set_preview(&candidate);
+        } else {
+            // Otherwise, fetch the feed in the background
+            self.previewing = true;
+            self.preview.clear();
+            let url = candidate.url;
+            let generation = self.next_generation();
+            sender.command(move |_out, shutdown| {
+                // Cancel the future if the component is shut down in the meantime
+                shutdown
+                    .register(async move {
+                        let result = discover::preview(&url).await;
+                        BROKER.send(Msg::Previewed(generation, result));
+                    })
+                    .drop_on_shutdown()
+            });
+        }
+    }
+    /// Pre-fill the title (unless the user has entered one) and preview the latest tidings
+    fn set_preview(&mut self, candidate: &Candidate) {
+        // Pre-fill the title
+        if let Some(ref title) = candidate.title {
+            if self.title.text().is_empty() {
+                self.title.set_text(title);
+            }
+        }
+        // List the latest tidings
+        self.preview = match candidate.items {
+            Some(ref items) if !items.is_empty() => items
+                .iter()
+                .map(|item| format!("\u{2022} {}", item))
+                .collect::<Vec<String>>()
+                .join("\n"),
+            Some(_) | None => String::from("There are no tidings in this feed yet"),
+        };
+    }
+}
+
+/// Get a clone of the Candidates List Box
+fn candidates_list_box(model: &Model) -> gtk::ListBox {
+    model.candidates.widget().clone()
+}
+
 #[allow(clippy::clone_on_ref_ptr)]
 #[allow(clippy::missing_docs_in_private_items)]
 #[allow(unused_variables)]
@@ -62,14 +165,23 @@ impl SimpleComponent for Model {
             title: gtk::EntryBuffer::default(),
             url: gtk::EntryBuffer::default(),
             allowed: false,
-            next_page: false,
+            page: Page::Url,
+            discovering: false,
+            error: None,
+            candidates: FactoryVecDeque::new(gtk::ListBox::new(), &sender.input),
+            previewing: false,
+            preview: String::from(""),
+            duplicate: None,
+            generation: 0,
         };
         let widgets = view_output!();
         // Set the names
-        let title_boxed_list_page = widgets.stack.page(&widgets.title_boxed_list);
+        let title_page = widgets.stack.page(&widgets.title_page);
         let url_boxed_list_page = widgets.stack.page(&widgets.url_boxed_list);
-        title_boxed_list_page.set_name("title_boxed_list");
+        let feeds_boxed_list_page = widgets.stack.page(&widgets.feeds_boxed_list);
+        title_page.set_name("title_page");
         url_boxed_list_page.set_name("url_boxed_list");
+        feeds_boxed_list_page.set_name("feeds_boxed_list");
         ComponentParts { model, widgets }
     }
     fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
@@ -79,30 +191,153 @@ impl SimpleComponent for Model {
                 // Hide the widget
                 self.visible = false;
                 // Get back to the first page
-                self.next_page = false;
+                self.page = Page::Url;
+                // Forget about the feeds found (the requests in flight are ignored)
+                self.next_generation();
+                self.discovering = false;
+                self.error = None;
+                self.candidates.guard().clear();
+                self.previewing = false;
+                self.preview.clear();
+                self.duplicate = None;
                 // Empty the buffers
                 self.title.delete_text(0, None);
                 self.url.delete_text(0, None);
             }
             Msg::Check => {
-                // If we're on the next page
-                self.allowed = if self.next_page {
+                // If we're on the title page
+                self.allowed = if self.page == Page::Title {
                     // Decide from the text of the title
                     !self.title.text().is_empty()
                 } else {
-                    // Decide from the text of the URL
-                    !self.url.text().is_empty()
+                    // Validate the URL (an empty one isn't an error yet)
+                    let text = self.url.text();
+                    match normalize::normalize(&text) {
+                        Ok(_) => {
+                            self.error = None;
+                            true
+                        }
+                        Err(error) => {
+                            self.error = (!text.trim().is_empty()).then_some(error);
+                            false
+                        }
+                    }
                 }
             }
             Msg::Next => {
-                // Proceed with adding data to the feed
-                self.next_page = true;
+                // Make sure the URL is valid
+                let url = match normalize::normalize(&self.url.text()) {
+                    Ok(url) => url,
+                    Err(error) => {
+                        self.error = Some(error);
+                        return;
+                    }
+                };
+                // Use the normalized URL from now on
+                self.url.set_text(&url);
+                // Look for the feeds in the background
+                self.discovering = true;
+                self.error = None;
+                let generation = self.next_generation();
+                sender.command(move |_out, shutdown| {
+                    // Cancel the future if the component is shut down in the meantime
+                    shutdown
+                        .register(async move {
+                            let result = discover::discover(&url).await;
+                            BROKER.send(Msg::Discovered(generation, result));
+                        })
+                        .drop_on_shutdown()
+                });
+            }
+            Msg::Discovered(generation, result) => {
+                // Ignore the results of the older requests (e.g., if
+                // the dialog has been closed and reopened in the meantime)
+                if !self.discovering || generation != self.generation {
+                    return;
+                }
+                self.discovering = false;
+                match result {
+                    Ok(mut candidates) => match candidates.len() {
+                        // There are no feeds here
+                        0 => {
+                            self.error = Some(String::from("No feeds found at this address"));
+                        }
+                        // There is exactly one, so use it
+                        1 => {
+                            if let Some(candidate) = candidates.pop() {
+                                self.show_title_page(candidate, &sender);
+                            }
+                        }
+                        // There are several, so let the user choose
+                        _ => {
+                            let mut candidates_guard = self.candidates.guard();
+                            candidates_guard.clear();
+                            for candidate in candidates {
+                                candidates_guard.push_back(candidate);
+                            }
+                            self.page = Page::Feeds;
+                        }
+                    },
+                    // Couldn't check the URL, so let the user know why
+                    Err(error) => {
+                        self.error = Some(format!("Couldn't check this address: {}", error));
+                    }
+                }
+            }
+            Msg::Select(index) => {
+                // If the candidate with this index still exists
+                if let Some(candidate) = usize::try_from(index)
+                    .ok()
+                    .and_then(|index| self.candidates.get(index))
+                    .cloned()
+                {
+                    // Proceed with adding data to the feed
+                    self.show_title_page(candidate, &sender);
+                }
+            }
+            Msg::Previewed(generation, result) => {
+                // Ignore the results of the older requests (e.g., if
+                // the dialog has been closed and reopened in the meantime)
+                if !self.previewing || generation != self.generation {
+                    return;
+                }
+                self.previewing = false;
+                match result {
+                    // Show the preview
+                    Ok(Some(candidate)) => self.set_preview(&candidate),
+                    // Let the user know that something is off
+                    Ok(None) => {
+                        self.preview = String::from("This doesn't look like a feed");
+                    }
+                    Err(_) => {
+                        self.preview = String::from("Couldn't fetch the feed for a preview");
+                    }
+                }
+            }
+            Msg::Duplicate(title, url) => {
+                // If we're still on the title page
+                if self.page == Page::Title {
+                    // Warn the user
+                    self.duplicate = Some((title, url));
+                }
+            }
+            Msg::GoToDuplicate => {
+                // If there is a duplicate
+                if let Some((_, url)) = self.duplicate.take() {
+                    // Show it in the sources list
+                    content::BROKER.send(content::Msg::GoToFeed(url));
+                    // Hide the dialog
+                    sender.input(Msg::Hide);
+                }
             }
             Msg::Add => {
                 // Get the title
                 let title = self.title.text();
-                // Get the URL
-                let url = self.url.text();
+                // Get the URL (a linked copy of a duplicate shares its URL, and thus its tidings)
+                let url = match self.duplicate {
+                    Some((_, ref url)) => url.clone(),
+                    None => self.url.text(),
+                };
                 // Add the source
                 content::BROKER.send(content::Msg::AddFeed(title, url));
                 // Hide the dialog
@@ -122,14 +357,24 @@ impl SimpleComponent for Model {
         }
         // If there is a visible child
         if let Some(visible_child_name) = stack.visible_child_name() {
-            // If we are to be on the next page and we're not there yet
-            if self.next_page && visible_child_name == "url_boxed_list" {
-                // Move to the next page
-                stack.set_visible_child(title_boxed_list);
-                // Update the default widget
-                add_feed_dialog.set_default_widget(Some(title_add_button));
-                // Let the first entry grab the focus
-                title_entry.grab_focus();
+            match self.page {
+                // If we are to choose a feed and we're not there yet
+                Page::Feeds if visible_child_name != "feeds_boxed_list" => {
+                    // Move to the list of feeds
+                    stack.set_visible_child(feeds_boxed_list);
+                    // There is nothing to activate by default here
+                    add_feed_dialog.set_default_widget(None::<&gtk::Widget>);
+                }
+                // If we are to be on the title page and we're not there yet
+                Page::Title if visible_child_name != "title_page" => {
+                    // Move to the title page
+                    stack.set_visible_child(title_page);
+                    // Update the default widget
+                    add_feed_dialog.set_default_widget(Some(title_add_button));
+                    // Let the first entry grab the focus
+                    title_entry.grab_focus();
+                }
+                Page::Url | Page::Feeds | Page::Title => {}
             }
         }
     }
@@ -169,6 +414,8 @@ impl SimpleComponent for Model {
                             // URL Action Row
                             append = &adw::ActionRow {
                                 set_title: "URL",
+                                #[watch]
+                                set_subtitle: model.error.as_deref().unwrap_or(""),
                                 // URL Title Entry
                                 add_suffix: url_entry = &gtk::Entry {
                                     set_margin_top: 7,
@@ -176,11 +423,21 @@ impl SimpleComponent for Model {
                                     set_buffer: &model.url,
                                     set_input_purpose: gtk::InputPurpose::Url,
                                     set_activates_default: true,
+                                    #[watch]
+                                    set_css_classes: if model.error.is_some() { &["error"] } else { &[] },
                                     // Check if adding the feed is allowed on an entry change
                                     connect_changed[sender] => move |_| {
                                         sender.input(Msg::Check);
                                     }
                                 },
+                                // Discovering Spinner
+                                add_suffix = &gtk::Spinner {
+                                    #[watch]
+                                    set_visible: model.discovering,
+                                    #[watch]
+                                    set_spinning: model.discovering,
+                                    set_tooltip_text: Some("Looking for feeds"),
+                                },
                                 // URL Add Button
                                 add_suffix: url_add_button = &gtk::Button {
                                     set_margin_top: 7,
@@ -188,7 +445,7 @@ impl SimpleComponent for Model {
                                     set_css_classes: &["suggested-action", "circular"],
                                     set_icon_name: "plus-large-symbolic",
                                     #[watch]
-                                    set_sensitive: model.allowed,
+                                    set_sensitive: model.allowed && !model.discovering,
                                     // Proceed with adding data to the feed
                                     connect_activate[sender] => move |_| {
                                         sender.input(Msg::Next);
@@ -196,39 +453,116 @@ impl SimpleComponent for Model {
                                 },
                             }
                         },
-                        // Title Boxed List
-                        add_child: title_boxed_list = &gtk::ListBox {
+                        // Feeds Boxed List
+                        add_child: feeds_boxed_list = &candidates_list_box(&model) -> gtk::ListBox {
                             set_selection_mode: gtk::SelectionMode::None,
                             add_css_class: "boxed-list",
-                            // Title Action Row
-                            append = &adw::ActionRow {
-                                set_title: "Title",
-                                // Feed Title Entry
-                                add_suffix: title_entry = &gtk::Entry {
-                                    set_margin_top: 7,
-                                    set_margin_bottom: 7,
-                                    set_buffer: &model.title,
-                                    set_input_purpose: gtk::InputPurpose::Name,
-                                    set_activates_default: true,
-                                    // Check if adding the feed is allowed on an entry change
-                                    connect_changed[sender] => move |_| {
-                                        sender.input(Msg::Check);
-                                    }
+                            connect_row_activated[sender] => move |_, row| {
+                                // Choose this feed
+                                sender.input(Msg::Select(row.index()));
+                            }
+                        },
+                        // Title Page
+                        add_child: title_page = &gtk::Box {
+                            set_orientation: gtk::Orientation::Vertical,
+                            set_spacing: 24,
+                            // Duplicate Boxed List
+                            append = &gtk::ListBox {
+                                #[watch]
+                                set_visible: model.duplicate.is_some(),
+                                set_selection_mode: gtk::SelectionMode::None,
+                                add_css_class: "boxed-list",
+                                // Duplicate Action Row
+                                append = &adw::ActionRow {
+                                    set_title: "Already Subscribed",
+                                    #[watch]
+                                    set_subtitle: model
+                                        .duplicate
+                                        .as_ref()
+                                        .map_or("", |&(ref title, _)| title.as_str()),
+                                    // Warning Icon
+                                    add_prefix = &gtk::Image {
+                                        set_icon_name: Some("dialog-warning-symbolic"),
+                                        add_css_class: "warning",
+                                    },
+                                    // Go To Feed Button
+                                    add_suffix = &gtk::Button {
+                                        set_label: "Go to Feed",
+                                        set_valign: gtk::Align::Center,
+                                        connect_clicked[sender] => move |_| {
+                                            sender.input(Msg::GoToDuplicate);
+                                        }
+                                    },
+                                }
+                            },
+                            // Title Boxed List
+                            append = &gtk::ListBox {
+                                set_selection_mode: gtk::SelectionMode::None,
+                                add_css_class: "boxed-list",
+                                // Title Action Row
+                                append = &adw::ActionRow {
+                                    set_title: "Title",
+                                    // Feed Title Entry
+                                    add_suffix: title_entry = &gtk::Entry {
+                                        set_margin_top: 7,
+                                        set_margin_bottom: 7,
+                                        set_buffer: &model.title,
+                                        set_input_purpose: gtk::InputPurpose::Name,
+                                        set_activates_default: true,
+                                        // Check if adding the feed is allowed on an entry change
+                                        connect_changed[sender] => move |_| {
+                                            sender.input(Msg::Check);
+                                        }
+                                    },
+                                    // Feed Add Button
+                                    add_suffix: title_add_button = &gtk::Button {
+                                        set_margin_top: 7,
+                                        set_margin_bottom: 7,
+                                        set_css_classes: &["suggested-action", "circular"],
+                                        set_icon_name: "plus-large-symbolic",
+                                        #[watch]
+                                        set_tooltip_text: if model.duplicate.is_some() {
+                                            Some("Add a Linked Copy")
+                                        } else {
+                                            Some("Add")
+                                        },
+                                        #[watch]
+                                        set_sensitive: model.allowed,
+                                        // Add on the press of the button
+                                        connect_activate[sender] => move |_| {
+                                            sender.input(Msg::Add);
+                                        }
+                                    },
+                                }
+                            },
+                            // Preview Box
+                            append = &gtk::Box {
+                                set_orientation: gtk::Orientation::Vertical,
+                                set_spacing: 12,
+                                // Preview Heading
+                                append = &gtk::Label {
+                                    set_label: "Latest Tidings",
+                                    set_xalign: 0.0,
+                                    add_css_class: "heading",
                                 },
-                                // Feed Add Button
-                                add_suffix: title_add_button = &gtk::Button {
-                                    set_margin_top: 7,
-                                    set_margin_bottom: 7,
-                                    set_css_classes: &["suggested-action", "circular"],
-                                    set_icon_name: "plus-large-symbolic",
+                                // Preview Spinner
+                                append = &gtk::Spinner {
                                     #[watch]
-                                    set_sensitive: model.allowed,
-                                    // Add on the press of the button
-                                    connect_activate[sender] => move |_| {
-                                        sender.input(Msg::Add);
-                                    }
+                                    set_visible: model.previewing,
+                                    #[watch]
+                                    set_spinning: model.previewing,
                                 },
-                            }
+                                // Preview Label
+                                append = &gtk::Label {
+                                    #[watch]
+                                    set_visible: !model.previewing,
+                                    #[watch]
+                                    set_label: &model.preview,
+                                    set_xalign: 0.0,
+                                    set_wrap: true,
+                                    add_css_class: "dim-label",
+                                },
+                            },
                         },
                     },
                 }
@@ -0,0 +1,61 @@
+//! Candidate feed
+
+use adw::prelude::{ActionRowExt, PreferencesRowExt};
+use gtk::traits::ListBoxRowExt;
+use relm4::factory::{DynamicIndex, FactoryComponent, FactoryComponentSender};
+
+/// Model
+#[derive(Debug, Clone)]
+pub struct Model {
+    /// URL of the feed
+    pub url: String,
+    /// Title of the feed (if known)
+    pub title: Option<String>,
+    /// Titles of the latest tidings (if the feed has been fetched)
+    pub items: Option<Vec<String>>,
+}
+
+/// Messages
+#[derive(Debug)]
+pub enum Msg {}
+
+#[allow(clippy::missing_docs_in_private_items)]
+#[relm4::factory(pub)]
+impl FactoryComponent for Model {
+    type CommandOutput = ();
+    type Init = Model;
+    type Input = Msg;
+    type Output = ();
+    type ParentMsg = super::Msg;
+    type ParentWidget = gtk::ListBox;
+    type Widgets = Widgets;
+    view! {
+        // Action Row
+        adw::ActionRow {
+            #[watch]
+            set_title: self.title.as_deref().unwrap_or(&self.url),
+            #[watch]
+            set_subtitle: &self.url,
+            set_activatable: true,
+            // Favicon
+            add_prefix = &gtk::Image {
+                set_icon_name: Some("emblem-shared-symbolic")
+            },
+            // Go Next Icon
+            add_suffix = &gtk::Image {
+                set_icon_name: Some("go-next-symbolic")
+            },
+        }
+    }
+    fn init_model(
+        candidate: Self::Init,
+        _index: &DynamicIndex,
+        _sender: FactoryComponentSender<Self>,
+    ) -> Self {
+        // The callers should construct the candidates themselves
+        candidate
+    }
+    fn update(&mut self, msg: Self::Input, _sender: FactoryComponentSender<Self>) {
+        match msg {}
+    }
+}
@@ -0,0 +1,137 @@
+//! Feed autodiscovery
+
+use reqwest::Url;
+use scraper::{Html, Selector};
+
+use std::time::Duration;
+
+use super::Candidate;
+
+/// Media types of the feeds advertised by the web pages
+const TYPES: [&str; 3] = [
+    "application/rss+xml",
+    "application/atom+xml",
+    "application/feed+json",
+];
+/// Common paths of the feeds (tried if a web page doesn't advertise any)
+const PATHS: [&str; 6] = [
+    "/feed",
+    "/rss",
+    "/rss.xml",
+    "/atom.xml",
+    "/feed.xml",
+    "/index.xml",
+];
+/// Timeout of a single request
+const TIMEOUT: Duration = Duration::from_secs(15);
+/// Number of the latest tidings to preview
+const PREVIEW_ITEMS: usize = 5;
+
+/// Find the feeds at this URL: either it points to a feed itself, or it's
+/// a web page that advertises some (or has them at the common paths)
+pub(super) async fn discover(url: &str) -> Result<Vec<Candidate>, reqwest::Error> {
+    // Prepare a client for the requests
+    let client = reqwest::Client::builder().timeout(TIMEOUT).build()?;
+    // Fetch the page
+    let response = client.get(url).send().await?.error_for_status()?;
+    let base = response.url().clone();
+    let bytes = response.bytes().await?;
+    // If it's a feed already, there is nothing else to find
+    if let Some(candidate) = parse(&bytes, &base) {
+        return Ok(vec![candidate]);
+    }
+    // Otherwise, look for the feeds advertised by the page
+    let mut candidates = advertised(&String::from_utf8_lossy(&bytes), &base);
+    // If there are none, probe the common paths
+    if candidates.is_empty() {
+        for path in PATHS {
+            if let Ok(url) = base.join(path) {
+                if let Some(candidate) = probe(&client, url).await {
+                    candidates.push(candidate);
+                }
+            }
+        }
+    }
+    // Several paths might lead to the same feed
+    let mut unique: Vec<Candidate> = Vec::with_capacity(candidates.len());
+    for candidate in candidates {
+        if !unique.iter().any(|other| other.url == candidate.url) {
+            unique.push(candidate);
+        }
+    }
+    Ok(unique)
+}
+
+/// Fetch the feed at this URL for a preview (nothing is returned if it's not a feed)
+pub(super) async fn preview(url: &str) -> Result<Option<Candidate>, reqwest::Error> {
+    // Prepare a client for the request
+    let client = reqwest::Client::builder().timeout(TIMEOUT).build()?;
+    // Fetch the feed
+    let response = client.get(url).send().await?.error_for_status()?;
+    let url = response.url().clone();
+    let bytes = response.bytes().await?;
+    Ok(parse(&bytes, &url))
+}
+
+/// Fetch the URL and check if there is a feed
+async fn probe(client: &reqwest::Client, url: Url) -> Option<Candidate> {
+    let response = client.get(url).send().await.ok()?.error_for_status().ok()?;
+    let url = response.url().clone();
+    let bytes = response.bytes().await.ok()?;
+    parse(&bytes, &url)
+}
+
+/// Parse the bytes as a feed at this URL
+fn parse(bytes: &[u8], url: &Url) -> Option<Candidate> {
+    let mut feed = feed_rs::parser::parse(bytes).ok()?;
+    // Put the latest entries first (the ones without dates go last)
+    feed.entries
+        .sort_by(|a, b| b.published.or(b.updated).cmp(&a.published.or(a.updated)));
+    Some(Candidate {
+        url: url.to_string(),
+        title: feed.title.map(|title| title.content),
+        items: Some(
+            feed.entries
+                .into_iter()
+                .filter_map(|entry| entry.title.map(|title| title.content))
+                .take(PREVIEW_ITEMS)
+                .collect(),
+        ),
+    })
+}
+
+/// Get the feeds advertised by the web page via the `<link rel="alternate">` elements
+fn advertised(text: &str, base: &Url) -> Vec<Candidate> {
+    // Prepare the selector
+    let selector = match Selector::parse(r#"link[rel~="alternate"][href]"#) {
+        Ok(selector) => selector,
+        Err(_) => return Vec::new(),
+    };
+    // Parse the page
+    let document = Html::parse_document(text);
+    document
+        .select(&selector)
+        .filter_map(|element| {
+            let element = element.value();
+            // Skip the links that aren't feeds (ignoring the parameters of the media type)
+            let media_type = element.attr("type")?.split(';').next()?.trim();
+            if !TYPES
+                .iter()
+                .any(|feed_type| feed_type.eq_ignore_ascii_case(media_type))
+            {
+                return None;
+            }
+            // Resolve the (possibly relative) reference
+            let url = base.join(element.attr("href")?.trim()).ok()?;
+            Some(Candidate {
+                url: url.to_string(),
+                title: element
+                    .attr("title")
+                    .map(str::trim)
+                    .filter(|title| !title.is_empty())
+                    .map(str::to_owned),
+                items: None,
+            })
+        })
+        .collect()
+}
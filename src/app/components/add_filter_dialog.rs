@@ -0,0 +1,333 @@
+//! Add Filter Dialog
+
+mod rule;
+
+use adw::prelude::{ActionRowExt, ComboRowExt, PreferencesRowExt};
+use gtk::prelude::{
+    BoxExt, ButtonExt, EditableExt, EntryBufferExtManual, EntryExt, GtkWindowExt, OrientableExt,
+    WidgetExt,
+};
+use relm4::factory::{DynamicIndex, FactoryVecDeque};
+use relm4::{ComponentParts, ComponentSender, MessageBroker, SimpleComponent};
+
+use super::content::{self, filters, tiding::normalize_tag};
+use super::AppMsg;
+use rule::Model as Rule;
+
+/// Message broker
+pub static BROKER: MessageBroker<Model> = MessageBroker::new();
+
+/// Titles of the actions (in the order they are shown to the user)
+const ACTIONS: [&str; 4] = ["Hide", "Mark as Read", "Star", "Tag"];
+/// Position of the tagging action
+const TAG_ACTION: u32 = 3;
+
+/// Model
+pub struct Model {
+    /// Is the window visible?
+    visible: bool,
+    /// Pattern entry buffer
+    pattern: gtk::EntryBuffer,
+    /// Is the pattern a regular expression?
+    regex: bool,
+    /// Position of the selected field
+    field: u32,
+    /// Position of the selected action
+    action: u32,
+    /// Tag entry buffer
+    tag: gtk::EntryBuffer,
+    /// Apply the filter to the tidings already stored?
+    retroactive: bool,
+    /// Error in the pattern (if any)
+    error: Option<String>,
+    /// Is the filter allowed to be added?
+    allowed: bool,
+    /// Rules of the filters already added
+    rules: FactoryVecDeque<Rule>,
+}
+
+/// Messages
+#[derive(Debug)]
+pub enum Msg {
+    /// Show the dialog
+    Show,
+    /// Hide the dialog
+    Hide,
+    /// Check if the filter is allowed to be added
+    Check,
+    /// Set whether the pattern is a regular expression
+    SetRegex(bool),
+    /// Select the field
+    SetField(u32),
+    /// Select the action
+    SetAction(u32),
+    /// Set whether to apply the filter to the tidings already stored
+    SetRetroactive(bool),
+    /// Add the filter
+    Add,
+    /// Show these rules of the filters (titles and subtitles)
+    SetRules(Vec<(String, String)>),
+    /// Remove the rule with this index
+    Remove(DynamicIndex),
+}
+
+/// Get a clone of the Rules List Box
+fn rules_list_box(model: &Model) -> gtk::ListBox {
+    model.rules.widget().clone()
+}
+
+#[allow(clippy::clone_on_ref_ptr)]
+#[allow(clippy::missing_docs_in_private_items)]
+#[allow(unused_variables)]
+#[relm4::component(pub)]
+impl SimpleComponent for Model {
+    type Init = ();
+    type Input = Msg;
+    type Output = AppMsg;
+    type Widgets = Widgets;
+    fn init(
+        _init: Self::Init,
+        root: &Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        // Initialize the model
+        let model = Self {
+            visible: false,
+            pattern: gtk::EntryBuffer::default(),
+            regex: false,
+            field: 0,
+            action: 0,
+            tag: gtk::EntryBuffer::default(),
+            retroactive: false,
+            error: None,
+            allowed: false,
+            rules: FactoryVecDeque::new(gtk::ListBox::new(), &sender.input),
+        };
+        let widgets = view_output!();
+        ComponentParts { model, widgets }
+    }
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            Msg::Show => self.visible = true,
+            Msg::Hide => {
+                // Hide the widget
+                self.visible = false;
+                // Empty the buffers
+                self.pattern.delete_text(0, None);
+                self.tag.delete_text(0, None);
+                // Reset the options
+                self.regex = false;
+                self.field = 0;
+                self.action = 0;
+                self.retroactive = false;
+                self.error = None;
+            }
+            Msg::Check => {
+                // The pattern is checked again on adding
+                self.error = None;
+                // A tag is required for tagging
+                self.allowed = !self.pattern.text().is_empty()
+                    && (self.action != TAG_ACTION || !normalize_tag(&self.tag.text()).is_empty());
+            }
+            Msg::SetRegex(regex) => {
+                self.regex = regex;
+                sender.input(Msg::Check);
+            }
+            Msg::SetField(field) => self.field = field,
+            Msg::SetAction(action) => {
+                self.action = action;
+                sender.input(Msg::Check);
+            }
+            Msg::SetRetroactive(retroactive) => self.retroactive = retroactive,
+            Msg::Add => {
+                // Get the field
+                let field = usize::try_from(self.field)
+                    .ok()
+                    .and_then(|position| filters::Field::ALL.get(position))
+                    .copied()
+                    .unwrap_or(filters::Field::Title);
+                // Get the action
+                let action = match self.action {
+                    0 => filters::Action::Hide,
+                    1 => filters::Action::MarkRead,
+                    2 => filters::Action::Star,
+                    _ => filters::Action::Tag(normalize_tag(&self.tag.text())),
+                };
+                // Prepare the pattern
+                match filters::Pattern::new(&self.pattern.text(), self.regex) {
+                    Ok(pattern) => {
+                        // Add the filter
+                        content::BROKER.send(content::Msg::AddFilter(
+                            field,
+                            pattern,
+                            action,
+                            self.retroactive,
+                        ));
+                        // Hide the dialog
+                        sender.input(Msg::Hide);
+                    }
+                    Err(error) => {
+                        // Show the error
+                        self.error = Some(error);
+                    }
+                }
+            }
+            Msg::SetRules(rules) => {
+                let mut rules_guard = self.rules.guard();
+                rules_guard.clear();
+                for rule in rules {
+                    rules_guard.push_back(rule);
+                }
+            }
+            Msg::Remove(index) => {
+                // Remove the rule from the list
+                let position = index.current_index();
+                self.rules.guard().remove(position);
+                // Stop applying it
+                content::BROKER.send(content::Msg::RemoveFilter(position));
+            }
+        }
+    }
+    fn pre_view() {
+        // Focus on the pattern entry when opening the dialog
+        if !add_filter_dialog.is_visible() {
+            pattern_entry.grab_focus();
+        }
+    }
+    view! {
+        add_filter_dialog = gtk::Dialog {
+            set_title: Some("Add New Filter"),
+            set_width_request: 313,
+            set_modal: true,
+            set_vexpand: false,
+            #[watch]
+            set_visible: model.visible,
+            set_default_widget: Some(&add_button),
+            connect_close_request[sender] => move |_| {
+                sender.input(Msg::Hide);
+                gtk::Inhibit(false)
+            },
+            // Clamp
+            #[wrap(Some)]
+            set_child = &adw::Clamp {
+                set_maximum_size: 400,
+                // Box
+                #[wrap(Some)]
+                set_child = &gtk::Box {
+                    set_orientation: gtk::Orientation::Vertical,
+                    set_margin_top: 24,
+                    set_margin_bottom: 24,
+                    set_margin_start: 12,
+                    set_margin_end: 12,
+                    set_spacing: 24,
+                    // Boxed List
+                    append = &gtk::ListBox {
+                        set_selection_mode: gtk::SelectionMode::None,
+                        add_css_class: "boxed-list",
+                        // Pattern Action Row
+                        append = &adw::ActionRow {
+                            set_title: "Pattern",
+                            #[watch]
+                            set_subtitle: model.error.as_deref().unwrap_or(""),
+                            // Pattern entry
+                            add_suffix: pattern_entry = &gtk::Entry {
+                                set_margin_top: 7,
+                                set_margin_bottom: 7,
+                                set_buffer: &model.pattern,
+                                set_activates_default: true,
+                                #[watch]
+                                set_css_classes: if model.error.is_some() { &["error"] } else { &[] },
+                                // Check if adding the filter is allowed on an entry change
+                                connect_changed[sender] => move |_| {
+                                    sender.input(Msg::Check);
+                                }
+                            },
+                        },
+                        // Regular Expression Action Row
+                        append = &adw::ActionRow {
+                            set_title: "Regular Expression",
+                            add_suffix = &gtk::Switch {
+                                set_valign: gtk::Align::Center,
+                                #[watch]
+                                set_active: model.regex,
+                                connect_active_notify[sender] => move |switch| {
+                                    sender.input(Msg::SetRegex(switch.is_active()));
+                                }
+                            },
+                        },
+                        // Field Combo Row
+                        append = &adw::ComboRow {
+                            set_title: "Look In",
+                            set_model: Some(&gtk::StringList::new(
+                                &filters::Field::ALL.map(filters::Field::title),
+                            )),
+                            #[watch]
+                            set_selected: model.field,
+                            connect_selected_notify[sender] => move |row| {
+                                sender.input(Msg::SetField(row.selected()));
+                            }
+                        },
+                        // Action Combo Row
+                        append = &adw::ComboRow {
+                            set_title: "Action",
+                            set_model: Some(&gtk::StringList::new(&ACTIONS)),
+                            #[watch]
+                            set_selected: model.action,
+                            connect_selected_notify[sender] => move |row| {
+                                sender.input(Msg::SetAction(row.selected()));
+                            }
+                        },
+                        // Tag Action Row
+                        append = &adw::ActionRow {
+                            #[watch]
+                            set_visible: model.action == TAG_ACTION,
+                            set_title: "Tag",
+                            // Tag entry
+                            add_suffix = &gtk::Entry {
+                                set_margin_top: 7,
+                                set_margin_bottom: 7,
+                                set_buffer: &model.tag,
+                                set_activates_default: true,
+                                // Check if adding the filter is allowed on an entry change
+                                connect_changed[sender] => move |_| {
+                                    sender.input(Msg::Check);
+                                }
+                            },
+                        },
+                        // Retroactive Action Row
+                        append = &adw::ActionRow {
+                            set_title: "Apply to Stored Tidings",
+                            add_suffix = &gtk::Switch {
+                                set_valign: gtk::Align::Center,
+                                #[watch]
+                                set_active: model.retroactive,
+                                connect_active_notify[sender] => move |switch| {
+                                    sender.input(Msg::SetRetroactive(switch.is_active()));
+                                }
+                            },
+                        },
+                    },
+                    // Add Button
+                    append: add_button = &gtk::Button {
+                        set_halign: gtk::Align::Center,
+                        set_css_classes: &["suggested-action", "pill"],
+                        set_label: "Add Filter",
+                        #[watch]
+                        set_sensitive: model.allowed,
+                        // Add on the press of the button
+                        connect_clicked[sender] => move |_| {
+                            sender.input(Msg::Add);
+                        }
+                    },
+                    // Rules Boxed List
+                    append = &rules_list_box(&model) -> gtk::ListBox {
+                        #[watch]
+                        set_visible: !model.rules.is_empty(),
+                        set_selection_mode: gtk::SelectionMode::None,
+                        add_css_class: "boxed-list",
+                    },
+                },
+            },
+        }
+    }
+}
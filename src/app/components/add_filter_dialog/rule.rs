@@ -0,0 +1,75 @@
+//! Rule (of the filters already added)
+
+use adw::prelude::{ActionRowExt, PreferencesRowExt};
+use gtk::prelude::{ButtonExt, WidgetExt};
+use relm4::factory::{DynamicIndex, FactoryComponent, FactoryComponentSender};
+
+/// Model
+#[derive(Debug)]
+pub struct Model {
+    /// Title (the pattern)
+    title: String,
+    /// Subtitle (the field and the action)
+    subtitle: String,
+    /// Index in the list
+    index: DynamicIndex,
+}
+
+/// Messages
+#[derive(Debug)]
+pub enum Msg {
+    /// Remove the rule
+    Remove,
+}
+
+#[allow(clippy::clone_on_ref_ptr)]
+#[allow(clippy::missing_docs_in_private_items)]
+#[relm4::factory(pub)]
+impl FactoryComponent for Model {
+    type CommandOutput = ();
+    type Init = (String, String);
+    type Input = Msg;
+    type Output = super::Msg;
+    type ParentMsg = super::Msg;
+    type ParentWidget = gtk::ListBox;
+    type Widgets = Widgets;
+    view! {
+        // Action Row
+        adw::ActionRow {
+            set_title: &self.title,
+            set_subtitle: &self.subtitle,
+            // Remove Button
+            add_suffix = &gtk::Button {
+                set_icon_name: "user-trash-symbolic",
+                set_tooltip_text: Some("Remove"),
+                set_valign: gtk::Align::Center,
+                add_css_class: "flat",
+                connect_clicked[sender] => move |_| {
+                    sender.input(Msg::Remove);
+                }
+            },
+        }
+    }
+    fn init_model(
+        (title, subtitle): Self::Init,
+        index: &DynamicIndex,
+        _sender: FactoryComponentSender<Self>,
+    ) -> Self {
+        Self {
+            title,
+            subtitle,
+            index: index.clone(),
+        }
+    }
+    fn update(&mut self, msg: Self::Input, sender: FactoryComponentSender<Self>) {
+        match msg {
+            Msg::Remove => {
+                // Ask the parent to remove the rule
+                sender.output(super::Msg::Remove(self.index.clone()));
+            }
+        }
+    }
+    fn output_to_parent_msg(output: Self::Output) -> Option<super::Msg> {
+        Some(output)
+    }
+}
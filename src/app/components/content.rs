@@ -1,44 +1,73 @@
 //! Leaflet
 
 mod dictionary;
+mod favicons;
+pub mod filters;
+mod list;
+mod query;
+mod retention;
+mod search;
+mod sections;
 pub mod source;
+mod tag;
 pub mod tiding;
+mod tree;
 mod update;
 
 use generational_arena::{Arena, Index};
-use gtk::prelude::{BoxExt, ButtonExt, ListBoxRowExt, OrientableExt, WidgetExt};
-use relm4::factory::{DynamicIndex, FactoryVecDeque};
+use gtk::prelude::{
+    BoxExt, ButtonExt, EditableExt, ListBoxRowExt, OrientableExt, SettingsExt, ToggleButtonExt,
+    WidgetExt,
+};
+use gtk::{gio, glib};
+use relm4::factory::FactoryVecDeque;
 use relm4::{
     ComponentParts, ComponentSender, MessageBroker, SimpleComponent, WidgetPlus, WorkerController,
 };
 use wyhash::WyHash;
 
-use std::collections::HashMap;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
 use std::hash::BuildHasherDefault;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 
-use super::AppMsg;
+use super::{add_feed_dialog, add_filter_dialog, tags_dialog, AppMsg};
 use crate::app::actions::{
-    ShowAboutDialog, ShowAddDirectoryDialog, ShowAddFeedDialog, ShowHelpOverlay,
+    RefreshInterval, RetentionPolicy, ShowAboutDialog, ShowAddDirectoryDialog, ShowAddFeedDialog,
+    ShowAddFilterDialog, ShowHelpOverlay,
 };
+use crate::config::APP_ID;
 use dictionary::Dictionary;
-use source::{ArenaSource, ListSource, URLsMap};
+use list::List;
+use query::Query;
+use retention::Policy;
+use source::{ArenaSource, ListSource};
+use tag::Model as Tag;
 use tiding::Model as Tiding;
+use tree::Tree;
+
+/// Time to collect the incoming results for before refreshing the lists with them
+const BATCH_DELAY: Duration = Duration::from_millis(250);
 
 /// Message broker
 pub static BROKER: MessageBroker<Model> = MessageBroker::new();
 
 /// Model
 pub struct Model {
+    /// Settings
+    settings: gio::Settings,
     /// Is the leaflet folded?
     folded: bool,
     /// Show tidings in the folded state?
     show_tidings: bool,
     /// Sources List (feeds and directories)
-    sources_list: FactoryVecDeque<ListSource>,
-    /// Dictionary of (Index, DynamicIndex) key-value pairs
-    sources_dictionary: HashMap<Index, DynamicIndex, BuildHasherDefault<WyHash>>,
+    sources_list: List<ListSource>,
     /// Sources Arena
     sources_arena: Arena<ArenaSource>,
+    /// Index of the tree of the sources in the arena
+    sources_tree: Tree,
     /// Sources subtitle
     sources_subtitle: String,
     /// Arena index of the root of all sources
@@ -48,109 +77,595 @@ pub struct Model {
     /// Arena index of the currently selected source
     current_source_index: Index,
     /// Tidings list
-    tidings_list: FactoryVecDeque<Tiding>,
+    tidings_list: List<Tiding>,
     /// Dictionary of (URL, Tiding) key-value pairs
     tidings_dictionary: Dictionary,
+    /// Filter rules, applied to every batch of the incoming tidings
+    filters: Vec<filters::Rule>,
+    /// Number of the filter rules created so far (used as their IDs)
+    filters_created: u64,
+    /// Tag browser (all tags of the tidings)
+    tags_list: FactoryVecDeque<Tag>,
+    /// Tag selected in the tag browser (shown instead of the current source)
+    current_tag: Option<String>,
+    /// Arena indices of the sources that show only the unread tidings
+    hide_read: HashSet<Index, BuildHasherDefault<WyHash>>,
+    /// Tags that show only the unread tidings
+    hide_read_tags: HashSet<String, BuildHasherDefault<WyHash>>,
+    /// Tidings (Feed URL, ID) marked as read in the current view while
+    /// the read ones are hidden (they are kept until the view changes)
+    kept_read: HashSet<(String, String), BuildHasherDefault<WyHash>>,
+    /// Full-text search index of the tidings (if it could be opened)
+    search_index: Option<search::Index>,
+    /// Error of opening the search index (if it couldn't be opened)
+    search_error: Option<String>,
+    /// Is the search bar shown?
+    search_mode: bool,
+    /// Search query
+    search_query: String,
+    /// Search across all sources instead of the selected one?
+    search_everywhere: bool,
     /// Is the update running?
     updating: bool,
     /// Number of requests handled (including failed ones)
     update_handled: usize,
     /// Number of nodes requested for update
     update_requested: usize,
-    /// Update message handler
-    update_worker: Option<WorkerController<update::Model>>,
+    /// URLs requested for update, but not handled yet
+    update_pending: HashSet<String, BuildHasherDefault<WyHash>>,
+    /// URLs that weren't refreshed because their update was cancelled
+    update_stale: HashSet<String, BuildHasherDefault<WyHash>>,
+    /// Update message handlers (by their IDs)
+    update_workers: HashMap<usize, WorkerController<update::Model>, BuildHasherDefault<WyHash>>,
+    /// ID of the next update message handler
+    update_next_id: usize,
+    /// Timer of the periodic update
+    refresh_timer: Option<glib::SourceId>,
+    /// Is the refresh of the lists with the batch of the incoming results scheduled?
+    batch_scheduled: bool,
+    /// Does the tag browser need a refresh once the batch is over?
+    batch_tags: bool,
+    /// Does the tidings list need a refresh once the batch is over?
+    batch_tidings: bool,
 }
 
 impl Model {
-    /// Get a dictionary of the (URL, Vec<Index>) pairs recursively
-    fn urls_map(&self) -> Option<URLsMap> {
-        // If the root source exists (as it always should!)
-        if let Some(root) = self.sources_arena.get(self.main_root_index) {
-            // Get a dictionary of the (URL, Vec<Index>) pairs recursively
-            let urls_map = root.urls_map(self.main_root_index, &self.sources_arena);
-            return Some(urls_map);
+    /// Start the update of the source (recursively, if it's a directory). Unless
+    /// forced, the feeds that aren't due according to their hints are skipped
+    fn start_update(&mut self, index: Index, forced: bool, sender: &ComponentSender<Self>) {
+        // If the source still exists
+        if let Some(source) = self.sources_arena.get(index) {
+            // Get a dictionary of the (URL, Vec<Index>) pairs of the feeds under the source
+            let mut urls_map = self.sources_tree.feeds(index).cloned().unwrap_or_default();
+            // Skip the URLs that are already being updated by another handler
+            urls_map.retain(|url, _| !self.update_pending.contains(url));
+            // Skip the feeds that are gone, unless one is updated directly
+            if source.is_dir() {
+                urls_map.retain(|_, indices| {
+                    indices.iter().any(|index| {
+                        self.sources_arena
+                            .get(*index)
+                            .map_or(false, |source| !source.is_gone())
+                    })
+                });
+            }
+            // Skip the feeds that aren't due yet, unless forced
+            if !forced {
+                let now = SystemTime::now();
+                urls_map.retain(|_, indices| {
+                    indices.iter().any(|index| {
+                        self.sources_arena
+                            .get(*index)
+                            .map_or(false, |source| source.is_due(now))
+                    })
+                });
+            }
+            // If there is something to update
+            if !urls_map.is_empty() {
+                // If no update is running, reset the progress bar
+                if !self.updating {
+                    self.update_requested = 0;
+                    self.update_handled = 0;
+                }
+                // Setup the progress bar
+                self.update_requested += urls_map.len();
+                // Remember the requested URLs
+                self.update_pending.extend(urls_map.keys().cloned());
+                // Prepare the requests, attaching the cache validators and the hints of the feeds
+                let mut requests: Vec<update::Request> = urls_map
+                    .into_iter()
+                    .map(|(url, indices)| {
+                        let feed = indices
+                            .iter()
+                            .find_map(|index| self.sources_arena.get(*index));
+                        update::Request {
+                            validators: feed
+                                .and_then(ArenaSource::validators)
+                                .cloned()
+                                .unwrap_or_default(),
+                            hints: feed
+                                .and_then(ArenaSource::hints)
+                                .cloned()
+                                .unwrap_or_default(),
+                            url,
+                            indices,
+                        }
+                    })
+                    .collect();
+                // Put the feeds that weren't refreshed last time first
+                requests.sort_by_key(|request| !self.update_stale.contains(&request.url));
+                // Create a new update message handler
+                let id = self.update_next_id;
+                self.update_next_id += 1;
+                let update = update::new(sender, id, update::Limits::from(&self.settings));
+                // Send the data to the update message handler
+                update.emit(update::Msg::UpdateAll(requests));
+                // Notify the UI that the update has started
+                self.updating = true;
+                // Let the model own the message handler
+                self.update_workers.insert(id, update);
+            }
+        }
+    }
+    /// Finish the update of the feeds with this URL (successfully, if there is
+    /// no error), remembering the time of the next allowed update (if known).
+    /// Returns `true` if some of the stored tidings have been pruned
+    fn finish_update(
+        &mut self,
+        indices: &[Index],
+        url: &str,
+        error: Option<update::Error>,
+        next_update: Option<SystemTime>,
+    ) -> bool {
+        // Prune the stored tidings of the feeds (even if there is
+        // nothing new, some of the stored tidings might have expired)
+        let pruned = self.prune(url, indices);
+        // This URL is no longer pending (if it isn't, the update has
+        // been cancelled, so the status of the feeds is already reset)
+        if !self.update_pending.remove(url) {
+            return pruned;
+        }
+        self.update_stale.remove(url);
+        // Increment the amount of handled requests
+        self.update_handled += 1;
+        let now = SystemTime::now();
+        // For each source
+        for index in indices {
+            // If there is a source with this index
+            if let Some(source) = self.sources_arena.get_mut(*index) {
+                // Remove the updating status of the feed
+                source.set_updating(false);
+                // Remember the error (or forget the previous one)
+                source.set_error(error.clone());
+                // Count the status in the aggregated ones of the parents
+                self.sources_tree.set_status(*index, source);
+                // Back off the feeds that keep failing
+                let next_update = if error.is_some() {
+                    let backoff = now + update::backoff(source.failures());
+                    Some(next_update.map_or(backoff, |time| time.max(backoff)))
+                } else {
+                    next_update
+                };
+                // Remember the time of the next allowed update
+                if next_update.is_some() {
+                    source.set_next_update(next_update);
+                }
+            }
+        }
+        // Show the status in the sources list
+        self.refresh_source_rows(indices);
+        pruned
+    }
+    /// Move the feeds with this URL to the new one (they've been redirected permanently)
+    fn move_feeds(&mut self, indices: &[Index], old_url: &str, new_url: &str) {
+        // Rewrite the URL of each feed
+        for index in indices {
+            if let Some(source) = self.sources_arena.get_mut(*index) {
+                source.set_url(new_url.to_owned());
+                self.sources_tree.set_url(*index, old_url, new_url);
+            }
+        }
+        // Migrate the tidings
+        self.tidings_dictionary.rename(old_url, new_url);
+        // Index them under the new URL
+        if let Some(ref mut search_index) = self.search_index {
+            search_index.remove_feed(old_url);
+            if let Some(tidings) = self.tidings_dictionary.get(new_url) {
+                search_index.add(tidings);
+            }
+        }
+        // Keep track of the update under the new URL
+        if self.update_pending.remove(old_url) {
+            self.update_pending.insert(new_url.to_owned());
+        }
+        if self.update_stale.remove(old_url) {
+            self.update_stale.insert(new_url.to_owned());
+        }
+        // Show the new URL in the sources list
+        self.refresh_source_rows(indices);
+        // The shown tidings might refer to the old URL (this is rare, so simply reload them)
+        self.refresh_tidings();
+    }
+    /// (Re)schedule the periodic update according to the settings
+    fn schedule_refresh(&mut self, sender: &ComponentSender<Self>) {
+        // Stop the previous timer
+        if let Some(refresh_timer) = self.refresh_timer.take() {
+            refresh_timer.remove();
+        }
+        // Get the interval (in minutes)
+        let interval = self.settings.uint("refresh-interval");
+        // If the periodic update is enabled
+        if interval > 0 {
+            // Start a new timer
+            let sender = sender.clone();
+            self.refresh_timer = Some(glib::timeout_add_seconds_local(
+                interval.saturating_mul(60),
+                move || {
+                    sender.input(Msg::PeriodicUpdate);
+                    glib::Continue(true)
+                },
+            ));
+        }
+    }
+    /// Schedule the refresh of the section titles of the tidings list at the next midnight
+    fn schedule_midnight(sender: &ComponentSender<Self>) {
+        // Fall back to an hour if the time is unknown
+        let seconds = sections::until_midnight().unwrap_or(60 * 60);
+        let sender = sender.clone();
+        glib::timeout_add_seconds_local_once(seconds, move || {
+            sender.input(Msg::Midnight);
+        });
+    }
+    /// Refresh the tag browser and the tidings list (if requested) once the current batch
+    /// of the incoming results is over, instead of doing it for every result
+    fn schedule_batch(&mut self, sender: &ComponentSender<Self>, tags: bool, tidings: bool) {
+        self.batch_tags |= tags;
+        self.batch_tidings |= tidings;
+        // If the refresh isn't scheduled yet
+        if !self.batch_scheduled {
+            self.batch_scheduled = true;
+            let sender = sender.clone();
+            glib::timeout_add_local_once(BATCH_DELAY, move || {
+                sender.input(Msg::FlushBatch);
+            });
         }
-        None
     }
     /// Refresh the list of sources with the sources under the current root
     fn refresh_sources(&mut self) {
-        // Get the sources list guard
-        let mut sources_guard = self.sources_list.guard();
         // If the current root still exists
         if let Some(root) = self.sources_arena.get(self.current_root_index) {
             // Update the subtitle
             self.sources_subtitle = root.title();
             // If the source has children
             if let Some(children) = root.children() {
-                // Clear the list of sources
-                sources_guard.clear();
-                // For each child
-                for child_index in children {
-                    // If the child source still exists
-                    if let Some(child_arena_source) = self.sources_arena.get(*child_index) {
-                        // Convert the child arena source to the child list source
-                        if let Some(child_list_source) =
-                            child_arena_source.clone().into_list_source(*child_index)
-                        {
-                            // Push the child source to the list
-                            sources_guard.push_back(child_list_source);
-                        }
+                // Convert the child arena sources (that still exist) to the list sources
+                let sources = children
+                    .iter()
+                    .filter_map(|child_index| {
+                        self.sources_arena
+                            .get(*child_index)?
+                            .to_list_source(*child_index, &self.sources_tree)
+                    })
+                    .collect();
+                // Change only the rows that differ
+                self.sources_list.update(sources, |source| *source.index());
+            }
+        // Otherwise,
+        } else {
+            // Render the list as empty
+            self.sources_list.clear();
+        }
+    }
+    /// Refresh the rows of these sources and their parents (recursively) in the sources list
+    fn refresh_source_rows(&mut self, indices: &[Index]) {
+        // Collect the indices of the sources and their parents
+        let mut affected: HashSet<Index, BuildHasherDefault<WyHash>> = HashSet::default();
+        for index in indices {
+            let mut next = Some(*index);
+            while let Some(current) = next {
+                // Stop if this branch has already been walked
+                if !affected.insert(current) {
+                    break;
+                }
+                next = self
+                    .sources_arena
+                    .get(current)
+                    .and_then(ArenaSource::parent_index)
+                    .copied();
+            }
+        }
+        // For each affected source
+        for index in affected {
+            // If the source is currently in the list
+            if let Some(position) = self
+                .sources_list
+                .position(|source| *source.index() == index)
+            {
+                // If the source still exists
+                if let Some(arena_source) = self.sources_arena.get(index) {
+                    // Convert the arena source to the list source
+                    if let Some(list_source) =
+                        arena_source.to_list_source(index, &self.sources_tree)
+                    {
+                        // Replace the row's copy of the source
+                        self.sources_list.set(position, list_source);
                     }
                 }
             }
+        }
+    }
+    /// Are the tidings of any of these sources shown in the tidings list?
+    fn shows_any(&self, indices: &[Index]) -> bool {
+        // If a tag is selected, any tiding might be tagged with it
+        if self.current_tag.is_some() {
+            true
+        // Otherwise, if the current source still exists
+        } else if let Some(current_source) = self.sources_arena.get(self.current_source_index) {
+            // If it's a saved search, any tiding might match it
+            if current_source.saved_search().is_some() {
+                true
+            // If it's a directory
+            } else if current_source.is_dir() {
+                // Check if any of the sources is a child (not necessarily a direct one) of it
+                indices.iter().any(|index| {
+                    self.sources_tree
+                        .is_child_of(*index, self.current_source_index)
+                })
+            // Otherwise, it's a feed, so
+            } else {
+                // Check if any of the sources is the current one
+                indices.contains(&self.current_source_index)
+            }
+        } else {
+            false
+        }
+    }
+    /// Get the search shown in the tidings list: the selected tag or the saved search (refined
+    /// by the search bar, if there is a query) or the query of the search bar, with the scope
+    fn current_search(&self) -> Option<(String, Option<Index>)> {
+        // Get the query of the search bar (if it's shown)
+        let query = self.search_query.trim();
+        let query = (self.search_mode && !query.is_empty()).then(|| query);
+        // If a tag is selected
+        if let Some(ref tag) = self.current_tag {
+            // Look for it everywhere
+            let tag_query = match query {
+                Some(query) => format!("tag:{} {}", tag, query),
+                None => format!("tag:{}", tag),
+            };
+            Some((tag_query, None))
+        // Otherwise, if the current source is a saved search
+        } else if let Some((saved_query, scope)) = self
+            .sources_arena
+            .get(self.current_source_index)
+            .and_then(ArenaSource::saved_search)
+        {
+            // Refine it with the query of the search bar
+            let saved_query = match query {
+                Some(query) => format!("{} {}", saved_query, query),
+                None => saved_query.to_owned(),
+            };
+            Some((saved_query, scope))
         // Otherwise,
         } else {
-            // Render the list as empty
-            sources_guard.clear();
+            // Search the selected source (or everywhere)
+            let scope = (!self.search_everywhere).then(|| self.current_source_index);
+            query.map(|query| (query.to_owned(), scope))
+        }
+    }
+    /// Get the tidings matching the query in the source with this index (or everywhere)
+    fn evaluate(&self, query: &Query, scope: Option<Index>) -> Vec<Tiding> {
+        // Search everywhere if there is no scope
+        let scope = scope.unwrap_or(self.main_root_index);
+        // Keep only the tidings that pass the filters
+        let now = SystemTime::now();
+        let passes = |tiding: &&Tiding| tiding.hidden_by.is_empty() && query.matches(tiding, now);
+        // If there is no full-text part
+        if query.text().is_empty() {
+            // Filter all tidings of the feeds
+            self.sources_tree
+                .urls(scope)
+                .filter_map(|url| self.tidings_dictionary.get(url))
+                .flatten()
+                .filter(passes)
+                .cloned()
+                .collect()
+        // Otherwise, if there is a full-text search index
+        } else if let Some(ref search_index) = self.search_index {
+            // Filter the results of the full-text search (skipping
+            // the ones that aren't in the dictionary anymore)
+            let mut results: Vec<&Tiding> = search_index
+                .search(query.text(), Some(self.sources_tree.urls(scope)))
+                .iter()
+                .filter_map(|&(ref url, ref id)| self.tidings_dictionary.find(url, id))
+                .filter(passes)
+                .collect();
+            // Keep the newest ones
+            results.sort_by_key(|tiding| Reverse(tiding.time()));
+            results.truncate(search::RESULTS);
+            results.into_iter().cloned().collect()
+        } else {
+            Vec::new()
+        }
+    }
+    /// Are the read tidings hidden in the current view?
+    fn hides_read(&self) -> bool {
+        match self.current_tag {
+            Some(ref tag) => self.hide_read_tags.contains(tag),
+            None => self.hide_read.contains(&self.current_source_index),
         }
     }
     /// Refresh the list of tidings with the tidings of the currently selected source
     fn refresh_tidings(&mut self) {
-        // Get the tidings list guard
-        let mut tidings_guard = self.tidings_list.guard();
-        // If the current source still exists
-        if let Some(source) = self.sources_arena.get(self.current_source_index) {
-            // Get the URL(s)
-            let urls = source.urls(&self.sources_arena);
-            // Clear the list of tidings
-            tidings_guard.clear();
+        // Make the changes of the search index visible
+        if let Some(ref mut search_index) = self.search_index {
+            search_index.commit();
+        }
+        // If the current source is gone, there are no tidings to show
+        let mut tidings: Vec<Tiding> = if !self.sources_arena.contains(self.current_source_index) {
+            Vec::new()
+        // If there is a search, evaluate it
+        } else if let Some((query, scope)) = self.current_search() {
+            self.evaluate(&Query::parse(&query), scope)
+        // Otherwise,
+        } else {
             // Load the tidings from the URLs
-            for url in urls {
-                // If there are tidings for this URL
-                if let Some(tidings) = self.tidings_dictionary.get(&url) {
-                    // Append each tiding to the list
-                    for tiding in tidings {
-                        tidings_guard.push_back(tiding.clone());
-                    }
+            self.sources_tree
+                .urls(self.current_source_index)
+                .filter_map(|url| self.tidings_dictionary.get(url))
+                .flatten()
+                .filter(|tiding| tiding.hidden_by.is_empty())
+                .cloned()
+                .collect()
+        };
+        // Hide the read tidings, if requested (except for the ones marked as read in this view)
+        if self.hides_read() {
+            tidings.retain(|tiding| {
+                !tiding.read
+                    || self
+                        .kept_read
+                        .contains(&(tiding.feed_url.clone(), tiding.id.clone()))
+            });
+        }
+        // Put the newest tidings first
+        tidings.sort_by_key(|tiding| Reverse(tiding.time()));
+        // Get the current local time (to group the tidings by day)
+        let now = glib::DateTime::now_local().ok();
+        let mut last_section = None;
+        // Get the favicons of the feeds
+        let feed_favicons: HashMap<&str, &Path, BuildHasherDefault<WyHash>> = self
+            .sources_arena
+            .iter()
+            .filter_map(|(_, source)| source.url().zip(source.favicon()))
+            .collect();
+        // Prepare each tiding for its row
+        for tiding in &mut tidings {
+            tiding.favicon = feed_favicons
+                .get(tiding.feed_url.as_str())
+                .copied()
+                .map(Path::to_path_buf);
+            // Start a new section on each new day
+            if let Some(ref now) = now {
+                let section = sections::title(tiding.time(), now);
+                if last_section.as_ref() != Some(&section) {
+                    tiding.section = Some(section.clone());
+                    last_section = Some(section);
                 }
             }
-        // Otherwise,
-        } else {
-            // Render the list as empty
-            tidings_guard.clear();
+        }
+        // Change only the rows that differ
+        self.tidings_list.update(tidings, |tiding| {
+            (tiding.feed_url.clone(), tiding.id.clone())
+        });
+    }
+    /// Change the row of the tiding (if it's in the tidings list)
+    fn update_tiding_row(&mut self, url: &str, id: &str, change: impl FnOnce(&mut Tiding)) {
+        if let Some(position) = self
+            .tidings_list
+            .position(|tiding| tiding.feed_url == url && tiding.id == id)
+        {
+            self.tidings_list.modify(position, change);
+        }
+    }
+    /// Refresh the tag browser with the tags of all tidings
+    fn refresh_tags(&mut self) {
+        // Get the tags list guard
+        let mut tags_guard = self.tags_list.guard();
+        // Clear the list of tags
+        tags_guard.clear();
+        // Append each tag to the list
+        for (name, count) in self.tidings_dictionary.tags() {
+            tags_guard.push_back(Tag { name, count });
+        }
+    }
+    /// Select the source, refreshing the tidings list (unless its tidings are already shown)
+    fn select_source(&mut self, index: Index) {
+        // If the source index is different from the currently selected
+        // one (or the tidings of a tag are shown instead)
+        if self.current_source_index != index || self.current_tag.is_some() {
+            // Update the current index
+            self.current_source_index = index;
+            self.current_tag = None;
+            // The view has changed, so the tidings marked as read can be hidden
+            self.kept_read.clear();
+            // Refresh the tidings list
+            self.refresh_tidings();
+        }
+    }
+    /// Prune the stored tidings of the feeds with this URL according to their
+    /// retention policy. Returns `true` if some of the tidings have been removed
+    fn prune(&mut self, url: &str, indices: &[Index]) -> bool {
+        // Prefer the policy of the feeds over the global one
+        let policy = indices
+            .iter()
+            .find_map(|index| {
+                self.sources_arena
+                    .get(*index)
+                    .and_then(ArenaSource::retention)
+            })
+            .or_else(|| Policy::parse(&self.settings.string("retention")))
+            .unwrap_or(Policy::Forever);
+        // Remove the tidings from the dictionary
+        let removed = self
+            .tidings_dictionary
+            .prune(url, policy, SystemTime::now());
+        // Remove them from the search index, too
+        if removed.is_empty() {
+            return false;
+        }
+        if let Some(ref mut search_index) = self.search_index {
+            search_index.remove(&removed);
+        }
+        true
+    }
+    /// Prune the stored tidings of all feeds according to their retention policies
+    fn prune_all(&mut self) {
+        // If the root source exists (as it always should!)
+        if let Some(urls_map) = self.sources_tree.feeds(self.main_root_index).cloned() {
+            // Prune the tidings of each feed
+            for (url, indices) in urls_map {
+                self.prune(&url, &indices);
+            }
+        }
+    }
+    /// Show the filter rules in the add filter dialog
+    fn show_filters(&self) {
+        add_filter_dialog::BROKER.send(add_filter_dialog::Msg::SetRules(
+            self.filters.iter().map(filters::Rule::describe).collect(),
+        ));
+    }
+    /// Apply the filter rule to the tidings already stored
+    fn apply_filter_to_stored(&mut self, rule: &filters::Rule) {
+        // Get the URLs of the feeds in the scope of the rule
+        let scope = rule.scope().unwrap_or(self.main_root_index);
+        // Apply the rule to the tidings of each feed
+        for url in self.sources_tree.urls(scope) {
+            if let Some(tidings) = self.tidings_dictionary.get_all_mut(url) {
+                rule.apply(tidings);
+            }
         }
     }
     /// Insert the source at this index
     fn insert_source(&mut self, new_arena_source: ArenaSource, new_list_index: usize) {
         // Insert the source into the arena
-        let new_arena_index = self.sources_arena.insert(new_arena_source.clone());
+        let new_arena_index = self.sources_arena.insert(new_arena_source);
+        // Add it to the index of the tree
+        if let Some(new_arena_source) = self.sources_arena.get(new_arena_index) {
+            self.sources_tree.insert(new_arena_index, new_arena_source);
+        }
         // Get the parent source
         if let Some(parent_source) = self.sources_arena.get_mut(self.current_root_index) {
             // Push the new index to the children of the parent
             parent_source.push_to_children(new_arena_index);
         }
         // Convert the arena source to the list source (should always succeed)
-        if let Some(new_list_source) = new_arena_source.into_list_source(new_arena_index) {
+        if let Some(new_list_source) = self
+            .sources_arena
+            .get(new_arena_index)
+            .and_then(|source| source.to_list_source(new_arena_index, &self.sources_tree))
+        {
             // Insert the new source at the specified index
-            let new_list_index = self
-                .sources_list
-                .guard()
-                .insert(new_list_index, new_list_source);
-            // Connect the two indices
-            self.sources_dictionary
-                .entry(new_arena_index)
-                .and_modify(|dyn_index| *dyn_index = new_list_index.clone())
-                .or_insert(new_list_index);
+            self.sources_list.insert(new_list_index, new_list_source);
         }
     }
     /// Add a new source to the list
@@ -169,13 +684,12 @@ impl Model {
             if let Some(parent_index) = current_arena_source.parent_index() {
                 // If the currently selected source is a direct child of the current root
                 if self.current_root_index == *parent_index {
-                    // If the dynamic index of the currently selected source is
-                    // stored in the sources dictionary (as it should be!)
-                    if let Some(current_list_dyn_index) =
-                        self.sources_dictionary.get(&self.current_source_index)
+                    // If the currently selected source is
+                    // in the sources list (as it should be!)
+                    if let Some(current_list_index) = self
+                        .sources_list
+                        .position(|source| *source.index() == self.current_source_index)
                     {
-                        // Get the current index of the dynamic index
-                        let current_list_index = current_list_dyn_index.current_index();
                         // Compute the list index of the new source
                         let new_list_index = current_list_index + 1;
                         // Insert the new source after the current one
@@ -197,6 +711,38 @@ impl Model {
             self.insert_source(new_arena_source, self.sources_list.len());
         }
     }
+    /// Show the feed with this URL in the sources list
+    fn go_to_feed(&mut self, url: &str) {
+        // Prefer the feeds under the currently selected source, falling back to all feeds
+        let index = self
+            .sources_tree
+            .feeds(self.current_source_index)
+            .and_then(|urls_map| urls_map.get(url))
+            .and_then(|indices| indices.first())
+            .or_else(|| self.sources_tree.nodes(url).first())
+            .copied();
+        // If there is a feed with this URL
+        if let Some(index) = index {
+            // If the feed has a parent (as it always should!)
+            if let Some(&parent_index) = self
+                .sources_arena
+                .get(index)
+                .and_then(ArenaSource::parent_index)
+            {
+                // If the parent isn't the current root
+                if self.current_root_index != parent_index {
+                    // Update the current root index
+                    self.current_root_index = parent_index;
+                    // Refresh the sources list
+                    self.refresh_sources();
+                }
+                // Select the feed
+                self.select_source(index);
+                // Show the sources list instead of the tidings if the leaflet is folded
+                self.show_tidings = false;
+            }
+        }
+    }
     /// Show the source by the index
     fn show_source(&mut self, index: Index) {
         // If the source still exists
@@ -217,21 +763,11 @@ impl Model {
                     // Refresh the sources list
                     self.refresh_sources();
                 }
-                // If the source index is different from the currently selected one
-                if self.current_source_index != index {
-                    // Update the current index
-                    self.current_source_index = index;
-                    // Refresh the tidings list
-                    self.refresh_tidings();
-                }
+                // Select the source
+                self.select_source(index);
             } else {
-                // If the source index is different from the currently selected one
-                if self.current_source_index != index {
-                    // Update the current index
-                    self.current_source_index = index;
-                    // Refresh the tidings list
-                    self.refresh_tidings();
-                }
+                // Select the source
+                self.select_source(index);
                 // If folded
                 if self.folded {
                     // Show the Tidings page
@@ -268,26 +804,92 @@ pub enum Msg {
     StopUpdateAll,
     /// Toggle the update of all feeds
     ToggleUpdateAll,
-    /// Update of the particular feed has started
-    UpdateStarted(Vec<Index>),
+    /// Start the periodic update of all feeds
+    PeriodicUpdate,
+    /// Reschedule the periodic update (the interval has changed)
+    RescheduleUpdate,
+    /// Refresh the lists with the batch of the incoming results
+    FlushBatch,
+    /// The clock has crossed midnight, so the titles of the sections have changed
+    Midnight,
+    /// Prune the stored tidings of all feeds (the global retention policy has changed)
+    PruneAll,
+    /// Set the retention policy of the feed (or use the global one)
+    SetRetention(Index, Option<Policy>),
+    /// Start the update of the source (recursively, if it's a directory)
+    StartUpdate(Index),
+    /// Start the update of the currently selected source
+    StartUpdateCurrent,
+    /// The update message handler with this ID has finished
+    UpdateFinished(usize),
+    /// Update of the particular feed by the message handler with this ID has started
+    UpdateStarted(usize, Vec<Index>),
+    /// Update of the particular feed has failed
+    UpdateFailed(Vec<Index>, String, update::Error),
+    /// The feeds have been redirected permanently from the first URL to the second one
+    Moved(Vec<Index>, String, String),
+    /// The favicon of the feeds has been fetched (and cached at this path)
+    FaviconFetched(Vec<Index>, PathBuf),
     //
     // Tidings specific:
     //
-    /// Insert the tidings at the specified URL
-    Insert(Vec<Index>, String, Vec<Tiding>),
+    /// Insert the tidings at the specified URL (with the new cache validators and hints)
+    Insert(Vec<Index>, String, update::Updated),
+    /// The feed at the specified URL hasn't changed since the last
+    /// update (with the time of the next allowed update)
+    NotModified(Vec<Index>, String, SystemTime),
     /// Show the tidings from the current root source
     ShowCurrentRoot,
     /// Show the tidings from the particular source in the list
-    ShowFromList(i32),
+    ShowFromList(u32),
+    /// Set the read status of the tiding (Feed URL, ID, status)
+    SetRead(String, String, bool),
+    /// Set the starred status of the tiding (Feed URL, ID, status)
+    SetStarred(String, String, bool),
+    /// Show the feed with this URL in the sources list
+    GoToFeed(String),
+    /// Show the tidings tagged with the particular tag in the tag browser
+    ShowTag(i32),
+    /// Open the tags dialog for the tiding (Feed URL, ID)
+    EditTags(String, String),
+    /// Set the tags of the tiding (Feed URL, ID, tags)
+    SetTags(String, String, Vec<String>),
+    /// Show or hide the search bar
+    ToggleSearch,
+    /// Set whether the search bar is shown
+    SetSearchMode(bool),
+    /// Search the tidings
+    Search(String),
+    /// Set whether to search across all sources
+    SetSearchEverywhere(bool),
+    /// Set whether to show only the unread tidings in the current view
+    SetHideRead(bool),
+    /// Add the filter rule for the current source (applying
+    /// it to the tidings already stored, if requested)
+    AddFilter(filters::Field, filters::Pattern, filters::Action, bool),
+    /// Remove the filter rule at this position
+    RemoveFilter(usize),
+    /// Save the current search as a source
+    SaveSearch,
+    //
+    // Add Feed Dialog specific:
+    //
+    /// Tell the dialog if a feed with this URL is already subscribed
+    FindDuplicate(String),
 }
 
-/// Get a clone of the Sources List Box
-fn sources_list_box(model: &Model) -> gtk::ListBox {
+/// Get a clone of the Sources List View
+fn sources_list_view(model: &Model) -> gtk::ListView {
     model.sources_list.widget().clone()
 }
 
-/// Get a clone of the Tidings List Box
-fn tidings_list_box(model: &Model) -> gtk::ListBox {
+/// Get a clone of the Tags List Box
+fn tags_list_box(model: &Model) -> gtk::ListBox {
+    model.tags_list.widget().clone()
+}
+
+/// Get a clone of the Tidings List View
+fn tidings_list_view(model: &Model) -> gtk::ListView {
     model.tidings_list.widget().clone()
 }
 
@@ -310,29 +912,84 @@ impl SimpleComponent for Model {
         // Prepare an arena with a root node
         let mut sources_arena = Arena::with_capacity(1);
         let root_source = ArenaSource::new_root();
-        let root_index = sources_arena.insert(root_source);
+        let mut sources_tree = Tree::new();
+        let root_index = sources_arena.insert(root_source.clone());
+        sources_tree.insert(root_index, &root_source);
+        // Open the search index (the error is shown in the search bar)
+        let (search_index, search_error) = match search::Index::open() {
+            Ok(search_index) => (Some(search_index), None),
+            Err(error) => (None, Some(error)),
+        };
         // Initialize the model
-        let model = Self {
+        let mut model = Self {
+            settings: gio::Settings::new(APP_ID),
             // Whether it's folded is restored on restart
             // by the `connect_folded_notify` function
             folded: false,
             show_tidings: false,
-            sources_list: FactoryVecDeque::new(gtk::ListBox::new(), &sender.input),
-            sources_dictionary: HashMap::default(),
+            sources_list: List::new({
+                let sender = sender.clone();
+                move |list_item| source::setup(list_item, &sender)
+            }),
             sources_arena,
+            sources_tree,
             sources_subtitle: String::from(""),
             main_root_index: root_index,
             current_root_index: root_index,
             current_source_index: root_index,
-            tidings_list: FactoryVecDeque::new(gtk::ListBox::new(), &sender.input),
+            tidings_list: List::new({
+                let sender = sender.clone();
+                move |list_item| tiding::setup(list_item, &sender)
+            }),
             tidings_dictionary: Dictionary::new(),
+            filters: Vec::new(),
+            filters_created: 0,
+            tags_list: FactoryVecDeque::new(gtk::ListBox::new(), &sender.input),
+            current_tag: None,
+            hide_read: HashSet::default(),
+            hide_read_tags: HashSet::default(),
+            kept_read: HashSet::default(),
+            search_index,
+            search_error,
+            search_mode: false,
+            search_query: String::from(""),
+            search_everywhere: false,
             updating: false,
             // Avoiding the nasty division by zero here
             update_handled: 0,
             update_requested: 1,
-            update_worker: None,
+            update_pending: HashSet::default(),
+            update_stale: HashSet::default(),
+            update_workers: HashMap::default(),
+            update_next_id: 0,
+            refresh_timer: None,
+            batch_scheduled: false,
+            batch_tags: false,
+            batch_tidings: false,
         };
+        // Schedule the periodic update
+        model.schedule_refresh(&sender);
+        // Reschedule it every time the interval is changed
+        model.settings.connect_changed(Some("refresh-interval"), {
+            let sender = sender.clone();
+            move |_, _| {
+                sender.input(Msg::RescheduleUpdate);
+            }
+        });
+        // Update the titles of the sections every midnight
+        Self::schedule_midnight(&sender);
+        // Prune the stored tidings at startup (this is a no-op until the tidings are
+        // persisted) and every time the global retention policy is changed
+        model.prune_all();
+        model.settings.connect_changed(Some("retention"), {
+            let sender = sender.clone();
+            move |_, _| {
+                sender.input(Msg::PruneAll);
+            }
+        });
         let widgets = view_output!();
+        // Let the search bar handle the entry
+        widgets.search_bar.connect_entry(&widgets.search_entry);
         // Make sure the separator page isn't navigatable
         let separator_page = widgets.leaflet.page(&widgets.separator);
         separator_page.set_navigatable(false);
@@ -375,31 +1032,67 @@ impl SimpleComponent for Model {
                 self.add_source(new_source);
             }
             Msg::StartUpdateAll => {
-                // Get a dictionary of the (URL, Vec<Index>) pairs recursively
-                if let Some(urls_map) = self.urls_map() {
-                    // If there is something to update
-                    if !urls_map.is_empty() {
-                        // Create a new update message handler
-                        let update = update::new(&sender);
-                        // Setup the progress bar
-                        self.update_requested = urls_map.len();
-                        self.update_handled = 0;
-                        // Send the data to the update message handler
-                        update.emit(update::Msg::UpdateAll(urls_map));
-                        // Notify the UI that the update has started
-                        self.updating = true;
-                        // Let the model own the message handler
-                        self.update_worker = Some(update);
-                    }
-                }
+                // Start the update from the root of all sources
+                self.start_update(self.main_root_index, true, &sender);
             }
             Msg::StopUpdateAll => {
-                // Drop the message handler (thus,
+                // Drop the message handlers (thus,
                 // cancelling any ongoing update)
-                self.update_worker = None;
+                self.update_workers.clear();
+                // Remember the feeds that weren't refreshed
+                self.update_stale.extend(self.update_pending.drain());
+                // Reset the updating status of the feeds
+                let mut indices = Vec::new();
+                for (index, source) in &mut self.sources_arena {
+                    if source.is_updating_feed() {
+                        source.set_updating(false);
+                        self.sources_tree.set_status(index, source);
+                        indices.push(index);
+                    }
+                }
+                // Show the status in the sources list
+                self.refresh_source_rows(&indices);
                 // Notify the UI that the update has been canceled
                 self.updating = false;
             }
+            Msg::PeriodicUpdate => {
+                // Skip this tick if an update is already in flight
+                if !self.updating {
+                    // Start the update of the feeds that are due
+                    self.start_update(self.main_root_index, false, &sender);
+                }
+            }
+            Msg::RescheduleUpdate => {
+                // Restart the timer with the new interval
+                self.schedule_refresh(&sender);
+            }
+            Msg::Midnight => {
+                // Update the titles of the sections
+                self.refresh_tidings();
+                // Do it again the next midnight
+                Self::schedule_midnight(&sender);
+            }
+            Msg::PruneAll => {
+                // Prune the stored tidings
+                self.prune_all();
+                // Show what's left
+                self.refresh_tags();
+                self.refresh_tidings();
+            }
+            Msg::SetRetention(index, policy) => {
+                // If there is a source with this index
+                if let Some(source) = self.sources_arena.get_mut(index) {
+                    // If the policy has changed
+                    if source.retention() != policy {
+                        // Set the policy
+                        source.set_retention(policy);
+                        // Show it in the sources list
+                        self.refresh_source_rows(&[index]);
+                        // Prune the stored tidings
+                        sender.input(Msg::PruneAll);
+                    }
+                }
+            }
             Msg::ToggleUpdateAll => {
                 if self.updating {
                     sender.input(Msg::StopUpdateAll);
@@ -407,60 +1100,123 @@ impl SimpleComponent for Model {
                     sender.input(Msg::StartUpdateAll);
                 }
             }
-            Msg::UpdateStarted(indices) => {
-                // For each index
-                for index in indices {
-                    // If there is a source with this index
-                    if let Some(source) = self.sources_arena.get_mut(index) {
-                        // Update the updating status
-                        source.set_updating(true);
+            Msg::StartUpdate(index) => {
+                // Start the update of this source only
+                self.start_update(index, true, &sender);
+            }
+            Msg::StartUpdateCurrent => {
+                // Start the update of the currently selected source
+                self.start_update(self.current_source_index, true, &sender);
+            }
+            Msg::UpdateFinished(id) => {
+                // Drop the message handler
+                self.update_workers.remove(&id);
+                // If this was the last one
+                if self.update_workers.is_empty() {
+                    // Notify the UI that the update is finished
+                    self.update_pending.clear();
+                    self.updating = false;
+                }
+            }
+            Msg::UpdateStarted(id, indices) => {
+                // Ignore the late messages of the message handlers
+                // that have been dropped (the update has been cancelled)
+                if self.update_workers.contains_key(&id) {
+                    // For each index
+                    for index in &indices {
+                        // If there is a source with this index
+                        if let Some(source) = self.sources_arena.get_mut(*index) {
+                            // Update the updating status
+                            source.set_updating(true);
+                            self.sources_tree.set_status(*index, source);
+                        }
                     }
+                    // Show the status in the sources list
+                    self.refresh_source_rows(&indices);
                 }
             }
-            Msg::Insert(indices, url, tidings) => {
+            Msg::UpdateFailed(indices, url, error) => {
+                // Finish the update with the error
+                let not_before = error.not_before();
+                if self.finish_update(&indices, &url, Some(error), not_before) {
+                    // Show what's left after the pruning
+                    let shown = self.shows_any(&indices);
+                    self.schedule_batch(&sender, true, shown);
+                }
+            }
+            Msg::Moved(indices, old_url, new_url) => {
+                // Rewrite the URL of the feeds, migrating their tidings
+                self.move_feeds(&indices, &old_url, &new_url);
+            }
+            Msg::NotModified(indices, url, next_update) => {
+                // Finish the update, there is nothing new
+                if self.finish_update(&indices, &url, None, Some(next_update)) {
+                    // Show what's left after the pruning
+                    let shown = self.shows_any(&indices);
+                    self.schedule_batch(&sender, true, shown);
+                }
+            }
+            Msg::Insert(indices, url, mut updated) => {
+                // Ignore the late results of the cancelled updates
+                if !self.update_pending.contains(&url) {
+                    return;
+                }
+                // Run the filters on the new batch
+                for rule in &self.filters {
+                    if rule.applies_to(&indices, &self.sources_tree) {
+                        rule.apply(&mut updated.tidings);
+                    }
+                }
                 // Insert the tidings into the dictionary, using the URL as a key
-                self.tidings_dictionary.insert(url, tidings);
-                // Increment the amount of handled requests
-                self.update_handled += 1;
+                let fresh = self.tidings_dictionary.insert(url.clone(), updated.tidings);
+                // Index the new tidings
+                if let Some(ref mut search_index) = self.search_index {
+                    search_index.add(fresh);
+                }
+                // Finish the update successfully (this prunes the stored tidings, too)
+                self.finish_update(&indices, &url, None, Some(updated.next_update));
                 // For each source
                 for index in &indices {
                     // If there is a source with this index
                     if let Some(source) = self.sources_arena.get_mut(*index) {
-                        // Remove the updating status of the feed
-                        source.set_updating(false);
+                        // Remember the cache validators and the hints for the next update
+                        source.set_validators(updated.validators.clone());
+                        source.set_hints(updated.hints.clone());
                     }
                 }
-                // If the current source still exists
-                if let Some(current_source) = self.sources_arena.get(self.current_source_index) {
-                    // If it's a directory
-                    if current_source.is_dir() {
-                        // For each index
-                        for index in indices {
-                            // If there is a source with this index
-                            if let Some(source) = self.sources_arena.get(index) {
-                                // If this source is a child (not necessarily a direct one) of the current source
-                                if source
-                                    .is_child_of(&self.current_source_index, &self.sources_arena)
-                                {
-                                    // Refresh the tidings list
-                                    self.refresh_tidings();
-                                    break;
-                                }
-                            }
-                        }
-                    // Otherwise, it's a feed, so
-                    } else {
-                        // For each index
-                        for index in indices {
-                            // If it matches the current one
-                            if index == self.current_source_index {
-                                // Refresh the tidings list
-                                self.refresh_tidings();
-                                break;
-                            }
-                        }
+                // The filters might have tagged some of them, so refresh the tag browser
+                // (and the tidings list, if the tidings of these sources are shown)
+                let shown = self.shows_any(&indices);
+                self.schedule_batch(&sender, true, shown);
+            }
+            Msg::FlushBatch => {
+                self.batch_scheduled = false;
+                // Commit the changes of the search index once per batch
+                if let Some(ref mut search_index) = self.search_index {
+                    search_index.commit();
+                }
+                // Refresh what the batch has changed
+                if mem::take(&mut self.batch_tags) {
+                    self.refresh_tags();
+                }
+                if mem::take(&mut self.batch_tidings) {
+                    self.refresh_tidings();
+                }
+            }
+            Msg::FaviconFetched(indices, path) => {
+                // Remember the path to the favicon
+                for index in &indices {
+                    if let Some(source) = self.sources_arena.get_mut(*index) {
+                        source.set_favicon(path.clone());
                     }
                 }
+                // Show the favicon in the sources list
+                self.refresh_source_rows(&indices);
+                // If the tidings of these sources are shown
+                if self.shows_any(&indices) {
+                    // Show the favicon in the tidings list, too
+                    self.schedule_batch(&sender, false, true);
+                }
             }
             Msg::ShowCurrentRoot => {
                 // Show the current root source
@@ -475,6 +1231,198 @@ impl SimpleComponent for Model {
                     self.show_source(index);
                 }
             }
+            Msg::SetRead(url, id, status) => {
+                // If the tiding is still in the dictionary
+                if let Some(tiding) = self.tidings_dictionary.get_mut(&url, &id) {
+                    // Update the read status
+                    tiding.read = status;
+                }
+                // Update the row of the tiding
+                self.update_tiding_row(&url, &id, |tiding| tiding.read = status);
+                // If the read tidings are hidden, keep this one until the view changes
+                if status && self.hides_read() {
+                    self.kept_read.insert((url, id));
+                }
+            }
+            Msg::SetStarred(url, id, status) => {
+                // If the tiding is still in the dictionary
+                if let Some(tiding) = self.tidings_dictionary.get_mut(&url, &id) {
+                    // Update the starred status
+                    tiding.starred = status;
+                }
+                // Update the row of the tiding
+                self.update_tiding_row(&url, &id, |tiding| tiding.starred = status);
+            }
+            Msg::GoToFeed(url) => {
+                // Show the feed in the sources list
+                self.go_to_feed(&url);
+            }
+            Msg::ShowTag(list_index) => {
+                // If the tag with this index still exists
+                if let Some(tag) = self.tags_list.get(list_index as usize) {
+                    // Show its tidings
+                    self.current_tag = Some(tag.name.clone());
+                    self.kept_read.clear();
+                    self.refresh_tidings();
+                    // If folded
+                    if self.folded {
+                        // Show the Tidings page
+                        self.show_tidings = true;
+                    }
+                }
+            }
+            Msg::EditTags(url, id) => {
+                // If the tiding is still in the dictionary
+                if let Some(tiding) = self.tidings_dictionary.find(&url, &id) {
+                    // Offer the known tags for the completion
+                    let known = self.tidings_dictionary.tags().into_keys().collect();
+                    // Open the dialog
+                    tags_dialog::BROKER.send(tags_dialog::Msg::Show(
+                        url,
+                        id,
+                        tiding.tags.clone(),
+                        known,
+                    ));
+                }
+            }
+            Msg::SetTags(url, id, tags) => {
+                // If the tiding is still in the dictionary
+                if let Some(tiding) = self.tidings_dictionary.get_mut(&url, &id) {
+                    // Update the tags
+                    tiding.tags = tags.clone();
+                }
+                // If the tidings of a tag are shown
+                if self.current_tag.is_some() {
+                    // The tiding might have been added to or removed from them
+                    self.refresh_tidings();
+                // Otherwise,
+                } else {
+                    // Update the row of the tiding
+                    self.update_tiding_row(&url, &id, |tiding| tiding.tags = tags);
+                }
+                // Update the tag browser
+                self.refresh_tags();
+            }
+            Msg::ToggleSearch => {
+                // Show or hide the search bar
+                self.search_mode = !self.search_mode;
+                // Show the results (or all tidings again)
+                self.refresh_tidings();
+            }
+            Msg::SetSearchMode(search_mode) => {
+                // If the search bar has been shown or hidden
+                if self.search_mode != search_mode {
+                    self.search_mode = search_mode;
+                    // Show the results (or all tidings again)
+                    self.refresh_tidings();
+                }
+            }
+            Msg::Search(query) => {
+                // Update the query
+                self.search_query = query;
+                // Show the results
+                self.refresh_tidings();
+            }
+            Msg::SetSearchEverywhere(everywhere) => {
+                // If the scope has changed
+                if self.search_everywhere != everywhere {
+                    self.search_everywhere = everywhere;
+                    // Show the results
+                    self.refresh_tidings();
+                }
+            }
+            Msg::SetHideRead(hide_read) => {
+                // If the choice has changed
+                if self.hides_read() != hide_read {
+                    // Remember it for the current source (or tag)
+                    match (self.current_tag.clone(), hide_read) {
+                        (Some(tag), true) => {
+                            self.hide_read_tags.insert(tag);
+                        }
+                        (Some(tag), false) => {
+                            self.hide_read_tags.remove(&tag);
+                        }
+                        (None, true) => {
+                            self.hide_read.insert(self.current_source_index);
+                        }
+                        (None, false) => {
+                            self.hide_read.remove(&self.current_source_index);
+                        }
+                    }
+                    // Show the tidings again
+                    self.kept_read.clear();
+                    self.refresh_tidings();
+                }
+            }
+            Msg::SaveSearch => {
+                // If there is a search
+                if let Some((query, scope)) = self.current_search() {
+                    // Create a new source, titled after the query
+                    let new_source = ArenaSource::new_search(
+                        query.clone(),
+                        query,
+                        scope,
+                        self.current_root_index,
+                    );
+                    // Add it to the list
+                    self.add_source(new_source);
+                }
+            }
+            Msg::AddFilter(field, pattern, action, retroactive) => {
+                // Limit the rule to the current source (or, if it's a saved
+                // search, to the current root), unless it's the root of all sources
+                let scope = if self
+                    .sources_arena
+                    .get(self.current_source_index)
+                    .and_then(ArenaSource::saved_search)
+                    .is_some()
+                {
+                    self.current_root_index
+                } else {
+                    self.current_source_index
+                };
+                let scope = (scope != self.main_root_index).then(|| scope);
+                // Create the rule
+                self.filters_created += 1;
+                let rule = filters::Rule::new(self.filters_created, field, pattern, action, scope);
+                // Apply it to the stored tidings, if requested
+                if retroactive {
+                    self.apply_filter_to_stored(&rule);
+                    self.refresh_tags();
+                    self.refresh_tidings();
+                }
+                // Remember it for the next updates
+                self.filters.push(rule);
+                self.show_filters();
+            }
+            Msg::RemoveFilter(position) => {
+                // Forget the rule
+                if position < self.filters.len() {
+                    let rule = self.filters.remove(position);
+                    // Show the tidings it has hidden again (the
+                    // effects of the other actions stay as they are)
+                    self.tidings_dictionary.unhide(rule.id());
+                    self.refresh_tidings();
+                }
+                self.show_filters();
+            }
+            Msg::FindDuplicate(url) => {
+                // Compare the URLs after normalization
+                let key = source::url_key(&url);
+                // If there is a feed with the same URL
+                if let Some((title, url)) = self.sources_arena.iter().find_map(|(_, source)| {
+                    source
+                        .url()
+                        .filter(|other| source::url_key(other) == key)
+                        .map(|url| (source.feed_title().unwrap_or_default(), url))
+                }) {
+                    // Warn the user about it
+                    add_feed_dialog::BROKER.send(add_feed_dialog::Msg::Duplicate(
+                        title.to_owned(),
+                        url.to_owned(),
+                    ));
+                }
+            }
         }
     }
     fn pre_view() {
@@ -602,17 +1550,47 @@ impl SimpleComponent for Model {
                     set_hscrollbar_policy: gtk::PolicyType::Never,
                     set_hexpand: true,
                     set_vexpand: true,
+                    // The list view must be the direct child to recycle its rows
                     #[wrap(Some)]
-                    set_child = &sources_list_box(&model) -> gtk::ListBox {
+                    set_child = &sources_list_view(&model) -> gtk::ListView {
                         #[watch]
                         set_visible: !model.sources_list.is_empty(),
+                        add_css_class: "navigation-sidebar",
+                        set_single_click_activate: true,
+                        connect_activate[sender] => move |_, position| {
+                            // Show the tidings from this source
+                            sender.input(Msg::ShowFromList(position));
+                        }
+                    },
+                },
+                // Tags Label
+                append = &gtk::Label {
+                    #[watch]
+                    set_visible: model.current_root_index == model.main_root_index
+                        && !model.tags_list.is_empty(),
+                    set_label: "Tags",
+                    set_halign: gtk::Align::Start,
+                    set_margin_start: 12,
+                    set_margin_top: 12,
+                    add_css_class: "heading",
+                },
+                // Tags Scrolled Window
+                append = &gtk::ScrolledWindow {
+                    #[watch]
+                    set_visible: model.current_root_index == model.main_root_index
+                        && !model.tags_list.is_empty(),
+                    set_hscrollbar_policy: gtk::PolicyType::Never,
+                    set_propagate_natural_height: true,
+                    set_max_content_height: 200,
+                    #[wrap(Some)]
+                    set_child = &tags_list_box(&model) -> gtk::ListBox {
                         set_selection_mode: gtk::SelectionMode::None,
                         add_css_class: "boxed-list",
                         set_margin_all: 12,
                         set_valign: gtk::Align::Start,
                         connect_row_activated[sender] => move |_, row| {
-                            // Show the tidings from this source
-                            sender.input(Msg::ShowFromList(row.index()));
+                            // Show the tidings tagged with this tag
+                            sender.input(Msg::ShowTag(row.index()));
                         }
                     },
                 }
@@ -659,6 +1637,69 @@ impl SimpleComponent for Model {
                             set_menu_model: Some(&main_menu),
                         },
                     },
+                    // Hide Read Button
+                    pack_end = &gtk::ToggleButton {
+                        set_icon_name: "mail-unread-symbolic",
+                        set_tooltip_text: Some("Show Only Unread"),
+                        #[watch]
+                        set_active: model.hides_read(),
+                        connect_toggled[sender] => move |button| {
+                            sender.input(Msg::SetHideRead(button.is_active()));
+                        }
+                    },
+                    // Search Button
+                    pack_end = &gtk::ToggleButton {
+                        set_icon_name: "system-search-symbolic",
+                        set_tooltip_text: Some("Search"),
+                        #[watch]
+                        set_active: model.search_mode,
+                        connect_toggled[sender] => move |button| {
+                            sender.input(Msg::SetSearchMode(button.is_active()));
+                        }
+                    },
+                },
+                // Search Bar
+                append: search_bar = &gtk::SearchBar {
+                    #[watch]
+                    set_search_mode: model.search_mode,
+                    connect_search_mode_enabled_notify[sender] => move |search_bar| {
+                        sender.input(Msg::SetSearchMode(search_bar.is_search_mode()));
+                    },
+                    #[wrap(Some)]
+                    set_child = &gtk::Box {
+                        set_spacing: 6,
+                        // Search Entry
+                        append: search_entry = &gtk::SearchEntry {
+                            set_hexpand: true,
+                            // Let the user know if the full-text search isn't available
+                            set_placeholder_text: Some(model.search_error.as_deref().unwrap_or("Search Tidings")),
+                            set_tooltip_text: model.search_error.as_deref(),
+                            set_css_classes: if model.search_error.is_some() { &["error"] } else { &[] },
+                            connect_search_changed[sender] => move |entry| {
+                                sender.input(Msg::Search(entry.text().into()));
+                            }
+                        },
+                        // Search Everywhere Button
+                        append = &gtk::ToggleButton {
+                            set_label: "Everywhere",
+                            set_tooltip_text: Some("Search All Sources"),
+                            #[watch]
+                            set_active: model.search_everywhere,
+                            connect_toggled[sender] => move |button| {
+                                sender.input(Msg::SetSearchEverywhere(button.is_active()));
+                            }
+                        },
+                        // Save Search Button
+                        append = &gtk::Button {
+                            set_icon_name: "bookmark-new-symbolic",
+                            set_tooltip_text: Some("Save Search"),
+                            #[watch]
+                            set_sensitive: !model.search_query.trim().is_empty(),
+                            connect_clicked[sender] => move |_| {
+                                sender.input(Msg::SaveSearch);
+                            }
+                        },
+                    },
                 },
                 // Tidings Scrolled Window
                 append: tidings_scrolled_window = &gtk::ScrolledWindow {
@@ -666,13 +1707,10 @@ impl SimpleComponent for Model {
                     set_hexpand: true,
                     set_vexpand: true,
                     #[wrap(Some)]
-                    set_child = &tidings_list_box(&model) -> gtk::ListBox {
+                    set_child = &tidings_list_view(&model) -> gtk::ListView {
                         #[watch]
                         set_visible: !model.tidings_list.is_empty(),
-                        set_selection_mode: gtk::SelectionMode::None,
-                        add_css_class: "boxed-list",
-                        set_margin_all: 12,
-                        set_valign: gtk::Align::Start,
+                        add_css_class: "navigation-sidebar",
                     },
                 }
             }
@@ -680,12 +1718,29 @@ impl SimpleComponent for Model {
     }
     menu! {
         main_menu: {
-            "Keyboard Shortcuts" => ShowHelpOverlay,
-            "About Tidings" => ShowAboutDialog,
+            section! {
+                "Don't Update Automatically" => RefreshInterval(0_u32),
+                "Update Every 15 Minutes" => RefreshInterval(15_u32),
+                "Update Every 30 Minutes" => RefreshInterval(30_u32),
+                "Update Every Hour" => RefreshInterval(60_u32),
+                "Update Every 3 Hours" => RefreshInterval(180_u32),
+                "Update Every 6 Hours" => RefreshInterval(360_u32),
+            },
+            section! {
+                "Keep All Tidings" => RetentionPolicy(String::from("forever")),
+                "Keep the Last 100 Tidings of Each Feed" => RetentionPolicy(String::from("last:100")),
+                "Keep Tidings for a Week" => RetentionPolicy(String::from("days:7")),
+                "Keep Tidings for a Month" => RetentionPolicy(String::from("days:30")),
+            },
+            section! {
+                "Keyboard Shortcuts" => ShowHelpOverlay,
+                "About Tidings" => ShowAboutDialog,
+            }
         },
         add_menu: {
             "Feed" => ShowAddFeedDialog,
             "Directory" => ShowAddDirectoryDialog,
+            "Filter" => ShowAddFilterDialog,
         }
     }
 }
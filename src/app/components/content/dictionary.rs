@@ -1,18 +1,26 @@
 //! Dictionary of (URL, tidings) key-value pairs
 
-use std::collections::{hash_map::Entry, HashMap};
+use std::collections::{hash_map::Entry, BTreeMap, HashMap, HashSet};
 use std::hash::BuildHasherDefault;
+use std::mem;
+use std::time::SystemTime;
 use wyhash::WyHash;
 
+use super::retention::Policy;
 use super::tiding::Model as Tiding;
 
 /// Hash map type
 type HashMapType = HashMap<String, Vec<Tiding>, BuildHasherDefault<WyHash>>;
 
+/// Type of the sets of the IDs of the tidings
+type IDsSet = HashSet<String, BuildHasherDefault<WyHash>>;
+
 /// Dictionary of (Feed URL, tidings) key-value pairs
 pub(super) struct Dictionary {
     /// Inner hash map
     hash_map: HashMapType,
+    /// IDs of the pruned tidings the feeds still serve (so that they aren't added again)
+    pruned: HashMap<String, IDsSet, BuildHasherDefault<WyHash>>,
 }
 
 impl Dictionary {
@@ -20,21 +28,157 @@ impl Dictionary {
     pub(super) fn new() -> Self {
         Self {
             hash_map: HashMapType::default(),
+            pruned: HashMap::default(),
         }
     }
-    /// Insert a key-value pair into the dictionary
-    pub(super) fn insert(&mut self, url: String, tidings: Vec<Tiding>) {
+    /// Insert a key-value pair into the dictionary, merging the new tidings
+    /// with the ones already stored (returns the tidings that weren't stored before)
+    pub(super) fn insert(&mut self, url: String, mut tidings: Vec<Tiding>) -> Vec<&Tiding> {
+        // Skip the tidings that have been pruned already
+        if let Some(pruned) = self.pruned.get_mut(&url) {
+            // Forget the ones the feed doesn't serve anymore
+            {
+                let served: HashSet<&str, BuildHasherDefault<WyHash>> =
+                    tidings.iter().map(|tiding| tiding.id.as_str()).collect();
+                pruned.retain(|id| served.contains(id.as_str()));
+            }
+            tidings.retain(|tiding| !pruned.contains(&tiding.id));
+        }
         match self.hash_map.entry(url) {
             Entry::Occupied(v) => {
-                *v.into_mut() = tidings;
+                let stored = v.into_mut();
+                // Take the stored tidings out
+                let old_tidings = mem::take(stored);
+                // Find the positions of the stored tidings by their IDs
+                let positions: HashMap<&str, usize, BuildHasherDefault<WyHash>> = old_tidings
+                    .iter()
+                    .enumerate()
+                    .map(|(position, old)| (old.id.as_str(), position))
+                    .collect();
+                // Put the new tidings first, keeping the user's state of the known ones
+                let mut merged = Vec::with_capacity(tidings.len() + old_tidings.len());
+                // Remember which stored tidings are still in the feed
+                // and the positions of the ones that weren't stored
+                let mut known = vec![false; old_tidings.len()];
+                let mut fresh = Vec::new();
+                for mut tiding in tidings {
+                    match positions.get(tiding.id.as_str()) {
+                        Some(&position) => {
+                            if let Some(old) = old_tidings.get(position) {
+                                tiding.read = old.read;
+                                tiding.starred = old.starred;
+                                for id in &old.hidden_by {
+                                    if !tiding.hidden_by.contains(id) {
+                                        tiding.hidden_by.push(*id);
+                                    }
+                                }
+                                tiding.fetched = old.fetched;
+                                // Keep the old tags first
+                                let tags = mem::replace(&mut tiding.tags, old.tags.clone());
+                                for tag in tags {
+                                    if !tiding.tags.contains(&tag) {
+                                        tiding.tags.push(tag);
+                                    }
+                                }
+                            }
+                            if let Some(flag) = known.get_mut(position) {
+                                *flag = true;
+                            }
+                        }
+                        None => fresh.push(merged.len()),
+                    }
+                    merged.push(tiding);
+                }
+                // Keep the tidings that are no longer in the feed
+                merged.extend(
+                    old_tidings
+                        .into_iter()
+                        .zip(known)
+                        .filter_map(|(old, known)| (!known).then_some(old)),
+                );
+                *stored = merged;
+                // Get the tidings that weren't known
+                let stored: &Vec<Tiding> = stored;
+                fresh
+                    .into_iter()
+                    .filter_map(|position| stored.get(position))
+                    .collect()
             }
-            Entry::Vacant(v) => {
-                v.insert(tidings);
+            Entry::Vacant(v) => v.insert(tidings).iter().collect(),
+        }
+    }
+    /// Move the tidings of the feed to the new URL (merging
+    /// them with the ones already stored there, if any)
+    pub(super) fn rename(&mut self, old_url: &str, new_url: &str) {
+        if let Some(mut tidings) = self.hash_map.remove(old_url) {
+            // Point the tidings to the new URL
+            for tiding in &mut tidings {
+                tiding.feed_url = new_url.to_owned();
             }
+            self.insert(new_url.to_owned(), tidings);
+        }
+        // Move the IDs of the pruned tidings, too
+        if let Some(pruned) = self.pruned.remove(old_url) {
+            self.pruned
+                .entry(new_url.to_owned())
+                .or_default()
+                .extend(pruned);
         }
     }
     /// Get tidings from the Feed URL
     pub(super) fn get(&self, url: &str) -> Option<&[Tiding]> {
         self.hash_map.get(url).map(std::vec::Vec::as_slice)
     }
+    /// Get mutable tidings from the Feed URL
+    pub(super) fn get_all_mut(&mut self, url: &str) -> Option<&mut [Tiding]> {
+        self.hash_map.get_mut(url).map(std::vec::Vec::as_mut_slice)
+    }
+    /// Prune the tidings of the feed according to the retention policy, returning the removed ones
+    /// (they aren't added again as long as the feed serves them)
+    pub(super) fn prune(&mut self, url: &str, policy: Policy, now: SystemTime) -> Vec<Tiding> {
+        let removed = self
+            .hash_map
+            .get_mut(url)
+            .map(|tidings| policy.prune(tidings, now))
+            .unwrap_or_default();
+        // Remember them
+        if !removed.is_empty() {
+            self.pruned
+                .entry(url.to_owned())
+                .or_default()
+                .extend(removed.iter().map(|tiding| tiding.id.clone()));
+        }
+        removed
+    }
+    /// Show the tidings hidden by the filter rule with this ID again
+    pub(super) fn unhide(&mut self, rule_id: u64) {
+        for tiding in self.hash_map.values_mut().flatten() {
+            tiding.hidden_by.retain(|id| *id != rule_id);
+        }
+    }
+    /// Get all tags with the numbers of the tidings tagged with them (sorted by name)
+    pub(super) fn tags(&self) -> BTreeMap<String, usize> {
+        let mut tags = BTreeMap::new();
+        for tag in self
+            .hash_map
+            .values()
+            .flatten()
+            .flat_map(|tiding| &tiding.tags)
+        {
+            *tags.entry(tag.clone()).or_insert(0) += 1;
+        }
+        tags
+    }
+    /// Get the tiding with this ID from the Feed URL
+    pub(super) fn find(&self, url: &str, id: &str) -> Option<&Tiding> {
+        self.hash_map
+            .get(url)
+            .and_then(|tidings| tidings.iter().find(|tiding| tiding.id == id))
+    }
+    /// Get a mutable reference to the tiding with this ID from the Feed URL
+    pub(super) fn get_mut(&mut self, url: &str, id: &str) -> Option<&mut Tiding> {
+        self.hash_map
+            .get_mut(url)
+            .and_then(|tidings| tidings.iter_mut().find(|tiding| tiding.id == id))
+    }
 }
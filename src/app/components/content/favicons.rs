@@ -0,0 +1,107 @@
+//! Favicons of the feeds (cached on disk)
+
+use gtk::prelude::Cast;
+use gtk::{gio, glib};
+use reqwest::header::CONTENT_TYPE;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::config::APP_ID;
+
+/// Time after which a cached favicon is fetched again
+const MAX_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Get the path of the cached favicon of the feed (whether it exists or not)
+fn path(url: &str) -> PathBuf {
+    glib::user_cache_dir()
+        .join(APP_ID)
+        .join("favicons")
+        .join(format!("{:016x}", wyhash::wyhash(url.as_bytes(), 0)))
+}
+
+/// Get the path of the cached favicon of the feed (if there is one)
+pub(super) fn cached(url: &str) -> Option<PathBuf> {
+    let path = path(url);
+    // An empty file means that the feed doesn't have a favicon
+    fs::metadata(&path)
+        .map_or(false, |metadata| metadata.is_file() && metadata.len() > 0)
+        .then(|| path)
+}
+
+/// Should the favicon of the feed be fetched (again)?
+pub(super) fn is_stale(url: &str) -> bool {
+    fs::metadata(path(url))
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|time| time.elapsed().ok())
+        .map_or(true, |age| age > MAX_AGE)
+}
+
+/// Get the icon from the favicon (falling back to the symbolic one if there is none)
+pub(super) fn icon(favicon: Option<&Path>) -> gio::Icon {
+    match favicon {
+        Some(path) => gio::FileIcon::new(&gio::File::for_path(path)).upcast(),
+        None => gio::ThemedIcon::new("emblem-shared-symbolic").upcast(),
+    }
+}
+
+/// Get the candidate URLs of the favicon of the feed: its own icon first, then the
+/// favicon of the site (or of the host of the feed), and, as the last resort, its logo
+pub(super) fn candidates(feed: &feed_rs::model::Feed, url: &str) -> Vec<String> {
+    // Get the site of the feed
+    let site = feed
+        .links
+        .iter()
+        .find(|link| link.rel.as_deref() != Some("self"))
+        .and_then(|link| reqwest::Url::parse(&link.href).ok())
+        .or_else(|| reqwest::Url::parse(url).ok());
+    feed.icon
+        .iter()
+        .map(|icon| icon.uri.clone())
+        .chain(
+            site.and_then(|site| site.join("/favicon.ico").ok())
+                .map(String::from),
+        )
+        .chain(feed.logo.iter().map(|logo| logo.uri.clone()))
+        .collect()
+}
+
+/// Fetch the favicon of the feed from the first candidate URL that serves an image,
+/// caching it on disk. Returns the path to the favicon (if one has been found)
+pub(super) async fn fetch(
+    client: &reqwest::Client,
+    url: &str,
+    candidates: Vec<String>,
+) -> Option<PathBuf> {
+    // Find the first image
+    let mut favicon = None;
+    for candidate in candidates {
+        favicon = fetch_image(client, &candidate).await;
+        if favicon.is_some() {
+            break;
+        }
+    }
+    // Cache it (an empty file remembers that there is none)
+    let path = path(url);
+    let bytes = favicon.unwrap_or_default();
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await.ok()?;
+    }
+    tokio::fs::write(&path, &bytes).await.ok()?;
+    (!bytes.is_empty()).then(|| path)
+}
+
+/// Fetch the image at this URL
+async fn fetch_image(client: &reqwest::Client, url: &str) -> Option<Vec<u8>> {
+    let response = client.get(url).send().await.ok()?.error_for_status().ok()?;
+    // Make sure it's an image
+    response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .filter(|content_type| content_type.starts_with("image/"))?;
+    let bytes = response.bytes().await.ok()?;
+    (!bytes.is_empty()).then(|| bytes.to_vec())
+}
@@ -0,0 +1,204 @@
+//! Rule-based filters of the tidings
+
+use generational_arena::Index;
+use regex::Regex;
+
+use super::tree::Tree;
+use super::Tiding;
+
+/// Part of the tiding the rule looks at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    /// Title
+    Title,
+    /// Summary and content
+    Content,
+    /// Names of the authors
+    Author,
+    /// Categories
+    Category,
+    /// Link
+    Link,
+}
+
+impl Field {
+    /// All fields (in the order they are shown to the user)
+    pub const ALL: [Self; 5] = [
+        Self::Title,
+        Self::Content,
+        Self::Author,
+        Self::Category,
+        Self::Link,
+    ];
+    /// Get the title of the field
+    pub const fn title(self) -> &'static str {
+        match self {
+            Self::Title => "Title",
+            Self::Content => "Content",
+            Self::Author => "Author",
+            Self::Category => "Category",
+            Self::Link => "Link",
+        }
+    }
+}
+
+/// What to do with the matching tidings
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// Hide them from the tidings list
+    Hide,
+    /// Mark them as read
+    MarkRead,
+    /// Star them
+    Star,
+    /// Tag them with this tag
+    Tag(String),
+}
+
+impl Action {
+    /// Get the title of the action
+    fn title(&self) -> String {
+        match *self {
+            Self::Hide => String::from("Hide"),
+            Self::MarkRead => String::from("Mark as Read"),
+            Self::Star => String::from("Star"),
+            Self::Tag(ref tag) => format!("Tag with {}", tag),
+        }
+    }
+}
+
+/// Pattern of the rule
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    /// Keyword (in lowercase), matched regardless of the case
+    Keyword(String),
+    /// Regular expression
+    Regex(Regex),
+}
+
+impl Pattern {
+    /// Create a pattern, either a keyword or a regular expression
+    pub fn new(text: &str, regex: bool) -> Result<Self, String> {
+        if regex {
+            Regex::new(text)
+                .map(Self::Regex)
+                .map_err(|error| format!("This isn't a valid regular expression: {}", error))
+        } else {
+            Ok(Self::Keyword(text.to_lowercase()))
+        }
+    }
+    /// Get the text of the pattern (the regular expressions are put between slashes)
+    fn text(&self) -> String {
+        match *self {
+            Self::Keyword(ref keyword) => keyword.clone(),
+            Self::Regex(ref regex) => format!("/{}/", regex.as_str()),
+        }
+    }
+    /// Does the text match the pattern?
+    fn is_match(&self, text: &str) -> bool {
+        match *self {
+            Self::Keyword(ref keyword) => text.to_lowercase().contains(keyword.as_str()),
+            Self::Regex(ref regex) => regex.is_match(text),
+        }
+    }
+}
+
+/// Filter rule
+#[derive(Debug, Clone)]
+pub(super) struct Rule {
+    /// Identifier (unique within the run of the app)
+    id: u64,
+    /// Part of the tiding to look at
+    field: Field,
+    /// Pattern to look for
+    pattern: Pattern,
+    /// What to do with the matching tidings
+    action: Action,
+    /// Arena index of the feed or the directory the rule is limited to (if any)
+    scope: Option<Index>,
+}
+
+impl Rule {
+    /// Create a rule with this ID
+    pub(super) const fn new(
+        id: u64,
+        field: Field,
+        pattern: Pattern,
+        action: Action,
+        scope: Option<Index>,
+    ) -> Self {
+        Self {
+            id,
+            field,
+            pattern,
+            action,
+            scope,
+        }
+    }
+    /// Get the title (the pattern) and the subtitle (the field and the action) of the rule
+    pub(super) fn describe(&self) -> (String, String) {
+        (
+            self.pattern.text(),
+            format!("{}: {}", self.field.title(), self.action.title()),
+        )
+    }
+    /// Get the identifier of the rule
+    pub(super) const fn id(&self) -> u64 {
+        self.id
+    }
+    /// Get the arena index of the source the rule is limited to (if any)
+    pub(super) const fn scope(&self) -> Option<Index> {
+        self.scope
+    }
+    /// Does the rule apply to the tidings of any of these sources?
+    pub(super) fn applies_to(&self, indices: &[Index], tree: &Tree) -> bool {
+        self.scope.map_or(true, |scope| {
+            indices
+                .iter()
+                .any(|index| *index == scope || tree.is_child_of(*index, scope))
+        })
+    }
+    /// Does the tiding match the rule?
+    fn matches(&self, tiding: &Tiding) -> bool {
+        match self.field {
+            Field::Title => self.pattern.is_match(&tiding.title),
+            Field::Content => tiding
+                .summary
+                .iter()
+                .chain(tiding.content.iter())
+                .any(|text| self.pattern.is_match(text)),
+            Field::Author => tiding
+                .authors
+                .iter()
+                .any(|author| self.pattern.is_match(author)),
+            Field::Category => tiding
+                .categories
+                .iter()
+                .any(|category| self.pattern.is_match(category)),
+            Field::Link => tiding
+                .link
+                .as_ref()
+                .map_or(false, |link| self.pattern.is_match(link)),
+        }
+    }
+    /// Apply the action of the rule to the tidings that match it
+    pub(super) fn apply(&self, tidings: &mut [Tiding]) {
+        for tiding in tidings.iter_mut().filter(|tiding| self.matches(tiding)) {
+            match self.action {
+                Action::Hide => {
+                    // Remember which rule hides the tiding, so that removing the rule shows it again
+                    if !tiding.hidden_by.contains(&self.id) {
+                        tiding.hidden_by.push(self.id);
+                    }
+                }
+                Action::MarkRead => tiding.read = true,
+                Action::Star => tiding.starred = true,
+                Action::Tag(ref tag) => {
+                    if !tiding.tags.contains(tag) {
+                        tiding.tags.push(tag.clone());
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,254 @@
+//! List views (with the row widgets recycled for different items)
+
+use gtk::prelude::{Cast, StaticType};
+use gtk::{gio, glib};
+
+use std::collections::HashSet;
+use std::hash::Hash;
+use std::mem;
+
+/// Get a clone of the item shown in the row (if the row is bound to one)
+pub(super) fn item<T: Clone + 'static>(list_item: &gtk::ListItem) -> Option<T> {
+    list_item
+        .item()?
+        .downcast::<glib::BoxedAnyObject>()
+        .ok()
+        .map(|object| T::clone(&object.borrow()))
+}
+
+/// List of the items shown in a list view
+pub(super) struct List<T> {
+    /// Items (mirrored by the model)
+    items: Vec<T>,
+    /// Model of the list view
+    store: gio::ListStore,
+    /// List view
+    view: gtk::ListView,
+}
+
+impl<T: Clone + PartialEq + 'static> List<T> {
+    /// Create a list, setting up each row widget with the function
+    pub(super) fn new(setup: impl Fn(&gtk::ListItem) + 'static) -> Self {
+        let store = gio::ListStore::new(glib::BoxedAnyObject::static_type());
+        let factory = gtk::SignalListItemFactory::new();
+        factory.connect_setup(move |_, list_item| setup(list_item));
+        let view = gtk::ListView::new(Some(&gtk::NoSelection::new(Some(&store))), Some(&factory));
+        Self {
+            items: Vec::new(),
+            store,
+            view,
+        }
+    }
+    /// Get the list view
+    pub(super) const fn widget(&self) -> &gtk::ListView {
+        &self.view
+    }
+    /// Get the number of the items
+    pub(super) fn len(&self) -> usize {
+        self.items.len()
+    }
+    /// Is the list empty?
+    pub(super) fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+    /// Get the item at this position
+    pub(super) fn get(&self, position: usize) -> Option<&T> {
+        self.items.get(position)
+    }
+    /// Find the position of the first item that satisfies the predicate
+    pub(super) fn position(&self, predicate: impl Fn(&T) -> bool) -> Option<usize> {
+        self.items.iter().position(predicate)
+    }
+    /// Replace the items in the range with the new ones
+    fn splice(&mut self, position: usize, removed: usize, added: Vec<T>) {
+        splice_items(
+            &mut self.items,
+            &mut mirror(&self.store),
+            position,
+            removed,
+            added,
+        );
+    }
+    /// Insert the item at this position
+    pub(super) fn insert(&mut self, position: usize, item: T) {
+        self.splice(position.min(self.len()), 0, vec![item]);
+    }
+    /// Replace the item at this position (the row is rebound only if it has changed)
+    pub(super) fn set(&mut self, position: usize, item: T) {
+        set_item(&mut self.items, &mut mirror(&self.store), position, item);
+    }
+    /// Change the item at this position
+    pub(super) fn modify(&mut self, position: usize, change: impl FnOnce(&mut T)) {
+        if let Some(mut item) = self.items.get(position).cloned() {
+            change(&mut item);
+            self.set(position, item);
+        }
+    }
+    /// Show the new items, changing only the rows that differ (see `update_items`)
+    pub(super) fn update<K: Eq + Hash>(&mut self, items: Vec<T>, key: impl Fn(&T) -> K) {
+        update_items(&mut self.items, &mut mirror(&self.store), items, key);
+    }
+    /// Remove all items
+    pub(super) fn clear(&mut self) {
+        self.items.clear();
+        self.store.remove_all();
+    }
+}
+
+/// Get the function that mirrors the changes of the items in the model of the list view
+fn mirror<T: Clone + 'static>(store: &gio::ListStore) -> impl FnMut(usize, usize, &[T]) + '_ {
+    move |position, removed, added| {
+        let objects: Vec<glib::Object> = added
+            .iter()
+            .map(|item| glib::BoxedAnyObject::new(item.clone()).upcast())
+            .collect();
+        store.splice(
+            u32::try_from(position).unwrap_or(u32::MAX),
+            u32::try_from(removed).unwrap_or(u32::MAX),
+            &objects,
+        );
+    }
+}
+
+/// Replace the items in the range with the new ones, passing the
+/// change (the position, the number of the removed items and the added items) to the mirror
+fn splice_items<T>(
+    items: &mut Vec<T>,
+    mirror: &mut impl FnMut(usize, usize, &[T]),
+    position: usize,
+    removed: usize,
+    added: Vec<T>,
+) {
+    // Don't notify the mirror about nothing
+    if removed == 0 && added.is_empty() {
+        return;
+    }
+    mirror(position, removed, &added);
+    items
+        .splice(position..position + removed, added)
+        .for_each(drop);
+}
+
+/// Replace the item at this position (only if it has changed)
+fn set_item<T: PartialEq>(
+    items: &mut Vec<T>,
+    mirror: &mut impl FnMut(usize, usize, &[T]),
+    position: usize,
+    item: T,
+) {
+    if items.get(position).map_or(false, |old| *old != item) {
+        splice_items(items, mirror, position, 1, vec![item]);
+    }
+}
+
+/// Replace the items with the new ones, changing only the ones that differ. The items
+/// with the same key are considered to be the same item. The rows of the items that
+/// stay untouched keep the scroll position (the list view follows the row at the top
+/// of the viewport), so the items arriving above it don't push the visible ones away
+fn update_items<T: PartialEq, K: Eq + Hash>(
+    items: &mut Vec<T>,
+    mirror: &mut impl FnMut(usize, usize, &[T]),
+    new_items: Vec<T>,
+    key: impl Fn(&T) -> K,
+) {
+    // Remove the items that are gone (a run of them at a time)
+    let new_keys: HashSet<K> = new_items.iter().map(&key).collect();
+    let mut position = items.len();
+    while position > 0 {
+        let end = position;
+        while position > 0
+            && items
+                .get(position - 1)
+                .map_or(false, |item| !new_keys.contains(&key(item)))
+        {
+            position -= 1;
+        }
+        if position < end {
+            splice_items(items, mirror, position, end - position, Vec::new());
+        } else {
+            position -= 1;
+        }
+    }
+    // If the remaining items have been reordered, replace all of them
+    let old_keys: HashSet<K> = items.iter().map(&key).collect();
+    if !new_items
+        .iter()
+        .filter(|item| old_keys.contains(&key(item)))
+        .map(&key)
+        .eq(items.iter().map(&key))
+    {
+        let count = items.len();
+        splice_items(items, mirror, 0, count, new_items);
+        return;
+    }
+    // Otherwise, insert the new items (a run of them at a time) and update the rest
+    let mut added = Vec::new();
+    let mut position = 0;
+    for item in new_items {
+        if old_keys.contains(&key(&item)) {
+            let count = added.len();
+            splice_items(items, mirror, position, 0, mem::take(&mut added));
+            position += count;
+            set_item(items, mirror, position, item);
+            position += 1;
+        } else {
+            added.push(item);
+        }
+    }
+    splice_items(items, mirror, position, 0, added);
+}
+
+/// Tests of the list updates
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Update the items, returning the changes passed to the mirror
+    /// (the position, the number of the removed and of the added items)
+    fn update(
+        items: &mut Vec<(u32, char)>,
+        new_items: &[(u32, char)],
+    ) -> Vec<(usize, usize, usize)> {
+        let mut mirrored = items.clone();
+        let mut changes = Vec::new();
+        update_items(
+            items,
+            &mut |position, removed, added: &[(u32, char)]| {
+                changes.push((position, removed, added.len()));
+                mirrored.splice(position..position + removed, added.iter().copied());
+            },
+            new_items.to_vec(),
+            |&(key, _)| key,
+        );
+        // The mirror has to end up the same
+        assert_eq!(*items, new_items);
+        assert_eq!(mirrored, new_items);
+        changes
+    }
+
+    /// Only the rows that differ are changed
+    #[test]
+    fn update_in_place() {
+        let mut items = vec![(1, 'a'), (2, 'b'), (3, 'c')];
+        let changes = update(&mut items, &[(0, 'z'), (1, 'a'), (3, 'x'), (4, 'd')]);
+        assert_eq!(changes, vec![(1, 1, 0), (0, 0, 1), (2, 1, 1), (3, 0, 1)]);
+    }
+
+    /// The reordered items are replaced all at once
+    #[test]
+    fn update_reordered() {
+        let mut items = vec![(1, 'a'), (2, 'b'), (3, 'c')];
+        let changes = update(&mut items, &[(3, 'c'), (1, 'a'), (2, 'b')]);
+        assert_eq!(changes, vec![(0, 3, 3)]);
+    }
+
+    /// The items with the same keys are handled, too
+    #[test]
+    fn update_duplicate_keys() {
+        let mut items = vec![(1, 'a'), (1, 'b')];
+        update(&mut items, &[(1, 'c')]);
+        update(&mut items, &[(1, 'c'), (1, 'd'), (2, 'e')]);
+        update(&mut items, &[(2, 'e'), (1, 'c')]);
+        update(&mut items, &[]);
+    }
+}
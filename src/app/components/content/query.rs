@@ -0,0 +1,160 @@
+//! Queries of the searches
+
+use std::time::{Duration, SystemTime};
+
+use super::Tiding;
+
+/// Query of a search: the full-text part and the filters
+#[derive(Debug, Clone, Default)]
+pub(super) struct Query {
+    /// Full-text part
+    text: String,
+    /// Read status
+    read: Option<bool>,
+    /// Starred status
+    starred: Option<bool>,
+    /// Maximum age of the tidings
+    max_age: Option<Duration>,
+    /// Minimum age of the tidings
+    min_age: Option<Duration>,
+    /// Part of the name of an author (in lowercase)
+    author: Option<String>,
+    /// Category (in lowercase)
+    category: Option<String>,
+    /// Tag (in lowercase, like the tags themselves)
+    tag: Option<String>,
+}
+
+/// Parse the age, like `12h`, `7d` or `2w`
+fn parse_age(age: &str) -> Option<Duration> {
+    let hours = match age.chars().last()? {
+        'h' => 1,
+        'd' => 24,
+        'w' => 7 * 24,
+        _ => return None,
+    };
+    let number: u64 = age.get(..age.len().checked_sub(1)?)?.parse().ok()?;
+    Some(Duration::from_secs(number.checked_mul(hours * 60 * 60)?))
+}
+
+impl Query {
+    /// Parse the query. The filters are `is:read`, `is:unread`, `is:starred`,
+    /// `is:unstarred`, `newer:<age>` and `older:<age>` (like `12h`, `7d` or `2w`),
+    /// `author:<name>`, `category:<name>` and `tag:<name>`; everything else is
+    /// the full-text part
+    pub(super) fn parse(input: &str) -> Self {
+        let mut query = Self::default();
+        let mut text = Vec::new();
+        for word in input.split_whitespace() {
+            match word.split_once(':') {
+                Some(("is", "read")) => query.read = Some(true),
+                Some(("is", "unread")) => query.read = Some(false),
+                Some(("is", "starred")) => query.starred = Some(true),
+                Some(("is", "unstarred")) => query.starred = Some(false),
+                Some(("newer", age)) if parse_age(age).is_some() => {
+                    query.max_age = parse_age(age);
+                }
+                Some(("older", age)) if parse_age(age).is_some() => {
+                    query.min_age = parse_age(age);
+                }
+                Some(("author", author)) if !author.is_empty() => {
+                    query.author = Some(author.to_lowercase());
+                }
+                Some(("category", category)) if !category.is_empty() => {
+                    query.category = Some(category.to_lowercase());
+                }
+                Some(("tag", tag)) if !tag.is_empty() => {
+                    query.tag = Some(tag.to_lowercase());
+                }
+                Some(_) | None => text.push(word),
+            }
+        }
+        query.text = text.join(" ");
+        query
+    }
+    /// Get the full-text part of the query
+    pub(super) fn text(&self) -> &str {
+        &self.text
+    }
+    /// Does the tiding pass the filters of the query (at this time)?
+    pub(super) fn matches(&self, tiding: &Tiding, now: SystemTime) -> bool {
+        // Get the age of the tiding (the tidings from the future are considered new)
+        let age = now.duration_since(tiding.time()).unwrap_or_default();
+        self.read.map_or(true, |read| tiding.read == read)
+            && self
+                .starred
+                .map_or(true, |starred| tiding.starred == starred)
+            && self.max_age.map_or(true, |max_age| age <= max_age)
+            && self.min_age.map_or(true, |min_age| age >= min_age)
+            && self.author.as_ref().map_or(true, |author| {
+                tiding
+                    .authors
+                    .iter()
+                    .any(|name| name.to_lowercase().contains(author.as_str()))
+            })
+            && self.category.as_ref().map_or(true, |category| {
+                tiding
+                    .categories
+                    .iter()
+                    .any(|name| name.to_lowercase() == *category)
+            })
+            && self
+                .tag
+                .as_ref()
+                .map_or(true, |tag| tiding.tags.contains(tag))
+    }
+}
+
+/// Tests of the queries
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::UNIX_EPOCH;
+
+    /// Number of seconds in a day
+    const DAY: u64 = 24 * 60 * 60;
+
+    /// Get a tiding published this many days after the UNIX epoch
+    fn published(days: u64) -> Tiding {
+        let mut tiding =
+            Tiding::from_entry(feed_rs::model::Entry::default(), "https://example.com");
+        tiding.published = Some(UNIX_EPOCH + Duration::from_secs(days * DAY));
+        tiding
+    }
+
+    /// The filters are taken out, and the rest is the full-text part
+    #[test]
+    fn parse() {
+        let query = Query::parse("rust is:unread  newer:2w older:12h author:Jane gtk");
+        assert_eq!(query.text(), "rust gtk");
+        assert_eq!(query.read, Some(false));
+        assert_eq!(query.max_age, Some(Duration::from_secs(14 * DAY)));
+        assert_eq!(query.min_age, Some(Duration::from_secs(12 * 60 * 60)));
+        assert_eq!(query.author.as_deref(), Some("jane"));
+    }
+
+    /// The invalid and overflowing ages are left in the full-text part
+    #[test]
+    fn parse_invalid_ages() {
+        let input = "newer:7x older:w newer:99999999999999999999d older:9999999999999999w";
+        let query = Query::parse(input);
+        assert_eq!(query.max_age, None);
+        assert_eq!(query.min_age, None);
+        assert_eq!(query.text(), input);
+    }
+
+    /// The ages are counted from the time of the publication
+    #[test]
+    fn matches_ages() {
+        let now = UNIX_EPOCH + Duration::from_secs(30 * DAY);
+        let newer = Query::parse("newer:7d");
+        let older = Query::parse("older:7d");
+        assert!(newer.matches(&published(25), now));
+        assert!(!newer.matches(&published(20), now));
+        assert!(older.matches(&published(20), now));
+        assert!(!older.matches(&published(25), now));
+        // The tidings from the future are considered new
+        assert!(newer.matches(&published(40), now));
+        assert!(!older.matches(&published(40), now));
+    }
+}
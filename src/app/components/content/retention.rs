@@ -0,0 +1,130 @@
+//! Retention policy of the stored tidings
+
+use std::cmp::Reverse;
+use std::mem;
+use std::time::{Duration, SystemTime};
+
+use super::Tiding;
+
+/// Number of seconds in a day
+const DAY: u64 = 24 * 60 * 60;
+
+/// Retention policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Policy {
+    /// Keep all tidings
+    Forever,
+    /// Keep the last N tidings
+    Last(usize),
+    /// Keep the tidings for N days
+    Days(u64),
+}
+
+impl Policy {
+    /// Parse the policy from its value in the settings (`forever`, `last:<N>` or `days:<N>`)
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.split_once(':') {
+            None if value == "forever" => Some(Self::Forever),
+            Some(("last", count)) => count.parse().ok().map(Self::Last),
+            Some(("days", days)) => days.parse().ok().map(Self::Days),
+            Some(_) | None => None,
+        }
+    }
+    /// Remove the tidings the policy doesn't keep (leaving the rest sorted from
+    /// the newest to the oldest). The starred and tagged tidings are always kept.
+    /// Returns the removed tidings
+    pub(super) fn prune(self, tidings: &mut Vec<Tiding>, now: SystemTime) -> Vec<Tiding> {
+        // Sort the tidings from the newest to the oldest, so that the newest ones are kept
+        tidings.sort_by_key(|tiding| Reverse(tiding.time()));
+        let mut kept = 0;
+        let (kept_tidings, removed_tidings) = mem::take(tidings).into_iter().partition(|tiding| {
+            // Spare the starred and tagged tidings
+            if tiding.starred || !tiding.tags.is_empty() {
+                return true;
+            }
+            match self {
+                Self::Forever => true,
+                Self::Last(count) => {
+                    kept += 1;
+                    kept <= count
+                }
+                Self::Days(days) => {
+                    // Use the time of the fetch if the time of the publication is unknown
+                    now.duration_since(tiding.time()).map_or(true, |age| {
+                        age <= Duration::from_secs(days.saturating_mul(DAY))
+                    })
+                }
+            }
+        });
+        *tidings = kept_tidings;
+        removed_tidings
+    }
+}
+
+/// Tests of the retention policies
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::UNIX_EPOCH;
+
+    /// Get a tiding with this ID published this many days after the UNIX
+    /// epoch (starred or tagged, if requested)
+    fn tiding(id: &str, days: u64, starred: bool, tagged: bool) -> Tiding {
+        let mut tiding =
+            Tiding::from_entry(feed_rs::model::Entry::default(), "https://example.com");
+        tiding.id = id.to_owned();
+        tiding.published = Some(UNIX_EPOCH + Duration::from_secs(days * DAY));
+        tiding.starred = starred;
+        if tagged {
+            tiding.tags.push(String::from("keep"));
+        }
+        tiding
+    }
+
+    /// Get the IDs of the tidings
+    fn ids(tidings: &[Tiding]) -> Vec<&str> {
+        tidings.iter().map(|tiding| tiding.id.as_str()).collect()
+    }
+
+    /// The policies are parsed from their values in the settings
+    #[test]
+    fn parse() {
+        assert_eq!(Policy::parse("forever"), Some(Policy::Forever));
+        assert_eq!(Policy::parse("last:100"), Some(Policy::Last(100)));
+        assert_eq!(Policy::parse("days:30"), Some(Policy::Days(30)));
+        assert_eq!(Policy::parse("days:soon"), None);
+        assert_eq!(Policy::parse("weeks:2"), None);
+        assert_eq!(Policy::parse("always"), None);
+    }
+
+    /// Only the newest tidings are kept, along with the starred and tagged ones
+    #[test]
+    fn last() {
+        let mut tidings = vec![
+            tiding("starred", 1, true, false),
+            tiding("newest", 4, false, false),
+            tiding("tagged", 2, false, true),
+            tiding("older", 3, false, false),
+        ];
+        let removed = Policy::Last(1).prune(&mut tidings, UNIX_EPOCH);
+        assert_eq!(ids(&tidings), vec!["newest", "tagged", "starred"]);
+        assert_eq!(ids(&removed), vec!["older"]);
+    }
+
+    /// Only the recent tidings are kept, along with the starred and tagged ones
+    #[test]
+    fn days() {
+        let mut tidings = vec![
+            tiding("old", 1, false, false),
+            tiding("recent", 4, false, false),
+            tiding("tagged", 2, false, true),
+            tiding("future", 20, false, false),
+        ];
+        let now = UNIX_EPOCH + Duration::from_secs(10 * DAY);
+        let removed = Policy::Days(7).prune(&mut tidings, now);
+        assert_eq!(ids(&tidings), vec!["future", "recent", "tagged"]);
+        assert_eq!(ids(&removed), vec!["old"]);
+        // Nothing is removed forever
+        assert!(Policy::Forever.prune(&mut tidings, now).is_empty());
+    }
+}
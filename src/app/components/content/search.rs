@@ -0,0 +1,210 @@
+//! Full-text search over the tidings (backed by an on-disk index)
+
+use gtk::glib;
+use tantivy::collector::DocSetCollector;
+use tantivy::directory::MmapDirectory;
+use tantivy::query::{BooleanQuery, Occur, Query, QueryParser, TermQuery};
+use tantivy::schema::{Field, IndexRecordOption, Schema, STORED, STRING, TEXT};
+use tantivy::{Document, IndexReader, IndexWriter, Term};
+
+use std::fs;
+use std::mem;
+
+use super::Tiding;
+use crate::config::APP_ID;
+
+/// Memory budget of the index writer (in bytes)
+const WRITER_MEMORY: usize = 16_000_000;
+/// Maximum number of the results of a search (the newest of the matching tidings are kept)
+pub(super) const RESULTS: usize = 1000;
+
+/// Fields of the documents
+struct Fields {
+    /// Unique key of the tiding (Feed URL and ID)
+    key: Field,
+    /// URL of the feed
+    feed_url: Field,
+    /// Identifier of the tiding (unique within the feed)
+    id: Field,
+    /// Title
+    title: Field,
+    /// Summary
+    summary: Field,
+    /// Content
+    content: Field,
+    /// Authors
+    authors: Field,
+}
+
+/// Search index
+pub(super) struct Index {
+    /// Inner index
+    index: tantivy::Index,
+    /// Writer of the index
+    writer: IndexWriter,
+    /// Reader of the index
+    reader: IndexReader,
+    /// Fields of the documents
+    fields: Fields,
+    /// Are there changes that haven't been committed yet?
+    dirty: bool,
+}
+
+/// Get the unique key of the tiding
+fn key(feed_url: &str, id: &str) -> String {
+    format!("{}\n{}", feed_url, id)
+}
+
+impl Index {
+    /// Open the index in the user data directory, creating it if necessary. The tidings
+    /// are only kept in memory for now, so the index starts empty (otherwise, the documents
+    /// of the previous runs would pile up). Returns the error if the index can't be opened
+    pub(super) fn open() -> Result<Self, String> {
+        // Prepare the schema
+        let mut builder = Schema::builder();
+        let fields = Fields {
+            key: builder.add_text_field("key", STRING),
+            feed_url: builder.add_text_field("feed_url", STRING | STORED),
+            id: builder.add_text_field("id", STORED),
+            title: builder.add_text_field("title", TEXT),
+            summary: builder.add_text_field("summary", TEXT),
+            content: builder.add_text_field("content", TEXT),
+            authors: builder.add_text_field("authors", TEXT),
+        };
+        let schema = builder.build();
+        // Open the index
+        let path = glib::user_data_dir().join(APP_ID).join("index");
+        fs::create_dir_all(&path)
+            .map_err(|error| format!("Couldn't create the search index: {}", error))?;
+        let directory = MmapDirectory::open(&path)
+            .map_err(|error| format!("Couldn't open the search index: {}", error))?;
+        let index = tantivy::Index::open_or_create(directory, schema)
+            .map_err(|error| format!("Couldn't open the search index: {}", error))?;
+        // Only one writer is allowed (another instance of the app might hold the lock)
+        let mut writer = index
+            .writer_with_num_threads(1, WRITER_MEMORY)
+            .map_err(|error| format!("Couldn't lock the search index: {}", error))?;
+        // Remove the documents of the previous runs
+        writer
+            .delete_all_documents()
+            .and_then(|_| writer.commit())
+            .map_err(|error| format!("Couldn't clear the search index: {}", error))?;
+        let reader = index
+            .reader()
+            .map_err(|error| format!("Couldn't read the search index: {}", error))?;
+        Ok(Self {
+            index,
+            writer,
+            reader,
+            fields,
+            dirty: false,
+        })
+    }
+    /// Add the tidings to the index (replacing the previous versions, if there are any).
+    /// The changes are visible to the searches only after the next commit
+    pub(super) fn add<'a>(&mut self, tidings: impl IntoIterator<Item = &'a Tiding>) {
+        for tiding in tidings {
+            // Remove the previous version
+            let key = key(&tiding.feed_url, &tiding.id);
+            self.writer
+                .delete_term(Term::from_field_text(self.fields.key, &key));
+            // Prepare the document
+            let mut document = Document::default();
+            document.add_text(self.fields.key, &key);
+            document.add_text(self.fields.feed_url, &tiding.feed_url);
+            document.add_text(self.fields.id, &tiding.id);
+            document.add_text(self.fields.title, &tiding.title);
+            if let Some(ref summary) = tiding.summary {
+                document.add_text(self.fields.summary, summary);
+            }
+            if let Some(ref content) = tiding.content {
+                document.add_text(self.fields.content, content);
+            }
+            for author in &tiding.authors {
+                document.add_text(self.fields.authors, author);
+            }
+            // Add it to the index
+            self.writer.add_document(document).ok();
+            self.dirty = true;
+        }
+    }
+    /// Remove the tidings from the index (after the next commit)
+    pub(super) fn remove<'a>(&mut self, tidings: impl IntoIterator<Item = &'a Tiding>) {
+        for tiding in tidings {
+            let key = key(&tiding.feed_url, &tiding.id);
+            self.writer
+                .delete_term(Term::from_field_text(self.fields.key, &key));
+            self.dirty = true;
+        }
+    }
+    /// Remove the tidings of the feed with this URL from the index (after the next commit)
+    pub(super) fn remove_feed(&mut self, feed_url: &str) {
+        self.writer
+            .delete_term(Term::from_field_text(self.fields.feed_url, feed_url));
+        self.dirty = true;
+    }
+    /// Commit the changes (if there are any), making them visible to the searches
+    pub(super) fn commit(&mut self) {
+        if mem::take(&mut self.dirty) && self.writer.commit().is_ok() {
+            self.reader.reload().ok();
+        }
+    }
+    /// Search for the tidings matching the query (only in the feeds with these URLs,
+    /// if specified). Returns the (Feed URL, ID) pairs of all of them (in no particular order)
+    pub(super) fn search<'a>(
+        &self,
+        query: &str,
+        urls: Option<impl Iterator<Item = &'a String>>,
+    ) -> Vec<(String, String)> {
+        // Parse the query, requiring all terms to match by default
+        let mut parser = QueryParser::for_index(
+            &self.index,
+            vec![
+                self.fields.title,
+                self.fields.summary,
+                self.fields.content,
+                self.fields.authors,
+            ],
+        );
+        parser.set_conjunction_by_default();
+        let query = match parser.parse_query(query) {
+            Ok(query) => query,
+            Err(_) => return Vec::new(),
+        };
+        // Limit the query to the feeds, if necessary
+        let query: Box<dyn Query> = match urls {
+            Some(urls) => {
+                let feeds = urls
+                    .map(|url| {
+                        let term = Term::from_field_text(self.fields.feed_url, url);
+                        let query: Box<dyn Query> =
+                            Box::new(TermQuery::new(term, IndexRecordOption::Basic));
+                        (Occur::Should, query)
+                    })
+                    .collect();
+                Box::new(BooleanQuery::new(vec![
+                    (Occur::Must, query),
+                    (Occur::Must, Box::new(BooleanQuery::new(feeds))),
+                ]))
+            }
+            None => query,
+        };
+        // Search for the documents, getting their keys (the results are
+        // limited by the caller, once the stale and filtered out ones are gone)
+        let searcher = self.reader.searcher();
+        searcher
+            .search(&query, &DocSetCollector)
+            .map(|documents| {
+                documents
+                    .into_iter()
+                    .filter_map(|address| {
+                        let document = searcher.doc(address).ok()?;
+                        let feed_url = document.get_first(self.fields.feed_url)?.as_text()?;
+                        let id = document.get_first(self.fields.id)?.as_text()?;
+                        Some((feed_url.to_owned(), id.to_owned()))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,51 @@
+//! Sections of the tidings list (by day)
+
+use gtk::glib;
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Number of days shown by the names of their weekdays (counting today and yesterday)
+const WEEK: i32 = 7;
+
+/// Get the title of the section of the day this time falls on: "Today", "Yesterday",
+/// the name of the weekday (within the last week), or the date
+pub(super) fn title(time: SystemTime, now: &glib::DateTime) -> String {
+    // Get the local date
+    let date = time
+        .duration_since(UNIX_EPOCH)
+        .ok()
+        .and_then(|duration| i64::try_from(duration.as_secs()).ok())
+        .and_then(|seconds| glib::DateTime::from_unix_local(seconds).ok());
+    let date = match date {
+        Some(date) => date,
+        None => return String::from("Older"),
+    };
+    // Find out how many days ago it was
+    let days_ago = (0..WEEK).find(|days| {
+        now.add_days(-days)
+            .map_or(false, |day| day.ymd() == date.ymd())
+    });
+    match days_ago {
+        Some(0) => String::from("Today"),
+        Some(1) => String::from("Yesterday"),
+        Some(_) => date.format("%A").map(String::from).unwrap_or_default(),
+        None => date.format("%x").map(String::from).unwrap_or_default(),
+    }
+}
+
+/// Get the number of seconds until the next local midnight (if it can be computed)
+pub(super) fn until_midnight() -> Option<u32> {
+    let now = glib::DateTime::now_local().ok()?;
+    let tomorrow = now.add_days(1).ok()?;
+    let midnight = glib::DateTime::from_local(
+        tomorrow.year(),
+        tomorrow.month(),
+        tomorrow.day_of_month(),
+        0,
+        0,
+        0.0,
+    )
+    .ok()?;
+    // Wake up right after midnight
+    u32::try_from(midnight.to_unix() - now.to_unix() + 1).ok()
+}
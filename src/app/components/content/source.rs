@@ -3,17 +3,54 @@
 #![allow(clippy::module_name_repetitions)]
 
 use adw::prelude::{ActionRowExt, PreferencesRowExt};
-use generational_arena::{Arena, Index};
-use gtk::prelude::ListBoxRowExt;
-use relm4::factory::{DynamicIndex, FactoryComponent, FactoryComponentSender};
+use generational_arena::Index;
+use gtk::prelude::{
+    BoxExt, ButtonExt, Cast, CheckButtonExt, GestureSingleExt, ObjectExt, OrientableExt,
+    PopoverExt, WidgetExt,
+};
+use gtk::{gdk, gio, glib};
+use relm4::ComponentSender;
 
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 use std::hash::BuildHasherDefault;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 use wyhash::WyHash;
 
+use super::retention::Policy;
+use super::tree::Tree;
+use super::update::{Error as UpdateError, Hints, Validators};
+use super::{favicons, list};
+
 /// A type alias to the dictionary of the (URL, Vec<Index>) key-value pairs
 pub(super) type URLsMap = HashMap<String, Vec<Index>, BuildHasherDefault<WyHash>>;
 
+/// Number of failures in a row after which the feed is considered failing
+const FAILING: u32 = 3;
+
+/// Get the key of the URL that is the same for the addresses that most likely point
+/// to the same feed (ignoring the scheme, the `www.` prefix and the trailing slash)
+pub(super) fn url_key(url: &str) -> String {
+    match reqwest::Url::parse(url) {
+        Ok(url) => {
+            let host = url.host_str().unwrap_or_default();
+            let host = host.strip_prefix("www.").unwrap_or(host);
+            let port = url
+                .port()
+                .map(|port| format!(":{}", port))
+                .unwrap_or_default();
+            let path = url.path().trim_end_matches('/');
+            let query = url
+                .query()
+                .map(|query| format!("?{}", query))
+                .unwrap_or_default();
+            format!("{}{}{}{}", host, port, path, query)
+        }
+        // Compare the invalid URLs as is
+        Err(_) => url.to_owned(),
+    }
+}
+
 /// Arena source
 #[derive(Debug, Clone)]
 pub enum ArenaSource {
@@ -25,6 +62,20 @@ pub enum ArenaSource {
         url: String,
         /// Is the feed in the process of being updated?
         updating: bool,
+        /// Error of the last update (if it failed)
+        error: Option<UpdateError>,
+        /// Number of failed updates in a row
+        failures: u32,
+        /// Cache validators of the last successful update
+        validators: Validators,
+        /// Update hints of the last successful update
+        hints: Hints,
+        /// Time of the next allowed update (if known)
+        next_update: Option<SystemTime>,
+        /// Path to the cached favicon (if there is one)
+        favicon: Option<PathBuf>,
+        /// Retention policy of the tidings (the global one, if there is none)
+        retention: Option<Policy>,
         /// Arena index of the parent directory
         parent_index: Index,
     },
@@ -37,6 +88,17 @@ pub enum ArenaSource {
         /// Arena index of the parent directory
         parent_index: Index,
     },
+    /// Saved search
+    Search {
+        /// Title
+        title: String,
+        /// Query (see `Query::parse`)
+        query: String,
+        /// Arena index of the source to search in (everywhere, if there is none)
+        scope: Option<Index>,
+        /// Arena index of the parent directory
+        parent_index: Index,
+    },
     /// Root directory
     RootDirectory {
         /// Children (counting recursively down)
@@ -54,69 +116,7 @@ impl ArenaSource {
             | Self::RootDirectory { ref mut children } => {
                 children.push(index);
             }
-            Self::Feed { .. } => {}
-        }
-    }
-    /// Get the URLs from the source recursively
-    pub(super) fn urls(&self, arena: &Arena<ArenaSource>) -> HashSet<String> {
-        match *self {
-            // If it's a feed, return its URL
-            Self::Feed { ref url, .. } => {
-                // Create a new hash set
-                let mut urls = HashSet::default();
-                // Insert the URL into the hash set
-                urls.insert(url.clone());
-                // Return the hash set
-                urls
-            }
-            // But if it's a directory,
-            Self::Directory { ref children, .. } | Self::RootDirectory { ref children, .. } => {
-                // Iterate over all children, collecting all their URLs
-                children
-                    .iter()
-                    .fold(HashSet::new(), |mut acc, child_index| {
-                        // If the child with this index still exists
-                        if let Some(child) = arena.get(*child_index) {
-                            // Extend the accumulator with their URL(s)
-                            acc.extend(child.urls(arena));
-                        }
-                        acc
-                    })
-            }
-        }
-    }
-    /// Get a dictionary of (URL, Vec<Index>) key-value pairs recursively
-    pub(super) fn urls_map(&self, index: Index, arena: &Arena<ArenaSource>) -> URLsMap {
-        match *self {
-            // If it's a feed
-            Self::Feed { ref url, .. } => {
-                // Create a new hash map
-                let mut urls_map = URLsMap::default();
-                // Insert an entry
-                urls_map.entry(url.clone()).or_insert_with(|| vec![index]);
-                // Return the hash map
-                urls_map
-            }
-            // But if it's a directory,
-            Self::Directory { ref children, .. } | Self::RootDirectory { ref children, .. } => {
-                // Iterate over all children, collecting all their (Index, URL) pairs
-                children
-                    .iter()
-                    .fold(URLsMap::default(), |mut acc, child_index| {
-                        // If the child with this index still exists
-                        if let Some(child) = arena.get(*child_index) {
-                            // Get the child hash map
-                            let urls_map = child.urls_map(*child_index, arena);
-                            // Merge the hash maps
-                            for (key, child_vec) in urls_map {
-                                acc.entry(key)
-                                    .and_modify(|vec| vec.extend_from_slice(&child_vec))
-                                    .or_insert(child_vec);
-                            }
-                        }
-                        acc
-                    })
-            }
+            Self::Feed { .. } | Self::Search { .. } => {}
         }
     }
     /// Get the children of directory
@@ -125,16 +125,30 @@ impl ArenaSource {
             Self::Directory { ref children, .. } | Self::RootDirectory { ref children, .. } => {
                 Some(children)
             }
-            Self::Feed { .. } => None,
+            Self::Feed { .. } | Self::Search { .. } => None,
         }
     }
     /// Get the title of the source
     pub(super) fn title(&self) -> String {
         match *self {
-            Self::Directory { ref title, .. } => title.clone(),
+            Self::Directory { ref title, .. } | Self::Search { ref title, .. } => title.clone(),
             Self::Feed { .. } | Self::RootDirectory { .. } => String::from(""),
         }
     }
+    /// Get the title of the feed
+    pub(super) fn feed_title(&self) -> Option<&str> {
+        match *self {
+            Self::Feed { ref title, .. } => Some(title),
+            Self::Directory { .. } | Self::RootDirectory { .. } | Self::Search { .. } => None,
+        }
+    }
+    /// Get the URL of the feed
+    pub(super) fn url(&self) -> Option<&str> {
+        match *self {
+            Self::Feed { ref url, .. } => Some(url),
+            Self::Directory { .. } | Self::RootDirectory { .. } | Self::Search { .. } => None,
+        }
+    }
     /// Get the index of the parent directory
     pub(super) fn parent_index(&self) -> Option<&Index> {
         match *self {
@@ -143,34 +157,74 @@ impl ArenaSource {
             }
             | Self::Directory {
                 ref parent_index, ..
+            }
+            | Self::Search {
+                ref parent_index, ..
             } => Some(parent_index),
             Self::RootDirectory { .. } => None,
         }
     }
+    /// Get the query and the scope of the saved search
+    pub(super) fn saved_search(&self) -> Option<(&str, Option<Index>)> {
+        match *self {
+            Self::Search {
+                ref query, scope, ..
+            } => Some((query, scope)),
+            Self::Feed { .. } | Self::Directory { .. } | Self::RootDirectory { .. } => None,
+        }
+    }
     /// Is the source a directory?
     pub(super) fn is_dir(&self) -> bool {
         matches!(self, &Self::Directory { .. } | &Self::RootDirectory { .. })
     }
-    /// Is this source a child (not necessarily a direct one) of the specified source?
-    pub(super) fn is_child_of(&self, index: &Index, arena: &Arena<ArenaSource>) -> bool {
-        // If this source has a parent
-        if let Some(parent_index) = self.parent_index() {
-            // If the parent still exists
-            if let Some(parent) = arena.get(*parent_index) {
-                // If the indexes match
-                if index == parent_index {
-                    true
-                // Otherwise, try one more level
+    /// Is the source a feed in the process of being updated?
+    pub(super) fn is_updating_feed(&self) -> bool {
+        matches!(self, &Self::Feed { updating: true, .. })
+    }
+    /// Is the source a feed that keeps failing to update?
+    pub(super) fn is_failing_feed(&self) -> bool {
+        match *self {
+            Self::Feed {
+                ref error,
+                failures,
+                ..
+            } => failures >= FAILING || matches!(*error, Some(UpdateError::Gone)),
+            Self::Directory { .. } | Self::RootDirectory { .. } | Self::Search { .. } => false,
+        }
+    }
+    /// Is the source a feed that is gone for good?
+    pub(super) fn is_gone(&self) -> bool {
+        matches!(
+            self,
+            &Self::Feed {
+                error: Some(UpdateError::Gone),
+                ..
+            }
+        )
+    }
+    /// Get the number of failed updates of the feed in a row
+    pub(super) fn failures(&self) -> u32 {
+        match *self {
+            Self::Feed { failures, .. } => failures,
+            Self::Directory { .. } | Self::RootDirectory { .. } | Self::Search { .. } => 0,
+        }
+    }
+    /// Get the error of the last update of the feed, prefixed by its title
+    pub(super) fn error_message(&self) -> Option<String> {
+        match *self {
+            Self::Feed {
+                ref title,
+                ref error,
+                failures,
+                ..
+            } => error.as_ref().map(|error| {
+                if failures > 1 {
+                    format!("{}: {} (failed {} times in a row)", title, error, failures)
                 } else {
-                    parent.is_child_of(index, arena)
+                    format!("{}: {}", title, error)
                 }
-            // Otherwise,
-            } else {
-                false
-            }
-        // Otherwise, there are no parents left, so
-        } else {
-            false
+            }),
+            Self::Directory { .. } | Self::RootDirectory { .. } | Self::Search { .. } => None,
         }
     }
     /// Set the updating status of the source
@@ -181,15 +235,138 @@ impl ArenaSource {
             } => {
                 *updating = status;
             }
-            Self::Directory { .. } | Self::RootDirectory { .. } => {}
+            Self::Directory { .. } | Self::RootDirectory { .. } | Self::Search { .. } => {}
+        }
+    }
+    /// Set the error of the last update of the source (counting the failures in a row)
+    pub(super) fn set_error(&mut self, new_error: Option<UpdateError>) {
+        match *self {
+            Self::Feed {
+                ref mut error,
+                ref mut failures,
+                ..
+            } => {
+                *failures = if new_error.is_some() {
+                    failures.saturating_add(1)
+                } else {
+                    0
+                };
+                *error = new_error;
+            }
+            Self::Directory { .. } | Self::RootDirectory { .. } | Self::Search { .. } => {}
+        }
+    }
+    /// Set the URL of the feed
+    pub(super) fn set_url(&mut self, new_url: String) {
+        match *self {
+            Self::Feed { ref mut url, .. } => {
+                *url = new_url;
+            }
+            Self::Directory { .. } | Self::RootDirectory { .. } | Self::Search { .. } => {}
+        }
+    }
+    /// Get the cache validators of the feed
+    pub(super) fn validators(&self) -> Option<&Validators> {
+        match *self {
+            Self::Feed { ref validators, .. } => Some(validators),
+            Self::Directory { .. } | Self::RootDirectory { .. } | Self::Search { .. } => None,
+        }
+    }
+    /// Set the cache validators of the feed
+    pub(super) fn set_validators(&mut self, new_validators: Validators) {
+        match *self {
+            Self::Feed {
+                ref mut validators, ..
+            } => {
+                *validators = new_validators;
+            }
+            Self::Directory { .. } | Self::RootDirectory { .. } | Self::Search { .. } => {}
+        }
+    }
+    /// Get the update hints of the feed
+    pub(super) fn hints(&self) -> Option<&Hints> {
+        match *self {
+            Self::Feed { ref hints, .. } => Some(hints),
+            Self::Directory { .. } | Self::RootDirectory { .. } | Self::Search { .. } => None,
+        }
+    }
+    /// Set the update hints of the feed
+    pub(super) fn set_hints(&mut self, new_hints: Hints) {
+        match *self {
+            Self::Feed { ref mut hints, .. } => {
+                *hints = new_hints;
+            }
+            Self::Directory { .. } | Self::RootDirectory { .. } | Self::Search { .. } => {}
+        }
+    }
+    /// Get the retention policy of the feed (if it has its own)
+    pub(super) fn retention(&self) -> Option<Policy> {
+        match *self {
+            Self::Feed { retention, .. } => retention,
+            Self::Directory { .. } | Self::RootDirectory { .. } | Self::Search { .. } => None,
+        }
+    }
+    /// Set the retention policy of the feed (or use the global one)
+    pub(super) fn set_retention(&mut self, policy: Option<Policy>) {
+        match *self {
+            Self::Feed {
+                ref mut retention, ..
+            } => {
+                *retention = policy;
+            }
+            Self::Directory { .. } | Self::RootDirectory { .. } | Self::Search { .. } => {}
+        }
+    }
+    /// Set the time of the next allowed update of the feed
+    pub(super) fn set_next_update(&mut self, time: Option<SystemTime>) {
+        match *self {
+            Self::Feed {
+                ref mut next_update,
+                ..
+            } => {
+                *next_update = time;
+            }
+            Self::Directory { .. } | Self::RootDirectory { .. } | Self::Search { .. } => {}
+        }
+    }
+    /// Is the source a feed that is allowed to be updated at this time?
+    pub(super) fn is_due(&self, now: SystemTime) -> bool {
+        match *self {
+            Self::Feed { next_update, .. } => next_update.map_or(true, |time| time <= now),
+            Self::Directory { .. } | Self::RootDirectory { .. } | Self::Search { .. } => false,
+        }
+    }
+    /// Get the path to the cached favicon of the feed (if there is one)
+    pub(super) fn favicon(&self) -> Option<&Path> {
+        match *self {
+            Self::Feed { ref favicon, .. } => favicon.as_deref(),
+            Self::Directory { .. } | Self::RootDirectory { .. } | Self::Search { .. } => None,
+        }
+    }
+    /// Set the path to the cached favicon of the feed
+    pub(super) fn set_favicon(&mut self, path: PathBuf) {
+        match *self {
+            Self::Feed {
+                ref mut favicon, ..
+            } => {
+                *favicon = Some(path);
+            }
+            Self::Directory { .. } | Self::RootDirectory { .. } | Self::Search { .. } => {}
         }
     }
-    /// Create a new feed source
+    /// Create a new feed source (with the favicon cached by the previous runs, if there is one)
     pub(super) fn new_feed(title: String, url: String, parent_index: Index) -> Self {
         Self::Feed {
             title,
+            favicon: favicons::cached(&url),
             url,
             updating: false,
+            error: None,
+            failures: 0,
+            validators: Validators::default(),
+            hints: Hints::default(),
+            next_update: None,
+            retention: None,
             parent_index,
         }
     }
@@ -201,32 +378,66 @@ impl ArenaSource {
             parent_index,
         }
     }
+    /// Create a new saved search source
+    pub(super) fn new_search(
+        title: String,
+        query: String,
+        scope: Option<Index>,
+        parent_index: Index,
+    ) -> Self {
+        Self::Search {
+            title,
+            query,
+            scope,
+            parent_index,
+        }
+    }
     /// Create a new root source
     pub(super) fn new_root() -> Self {
         Self::RootDirectory { children: vec![] }
     }
     /// Convert the arena source to the list source with the arena index
-    pub(super) fn into_list_source(self, index: Index) -> Option<ListSource> {
-        match self {
+    /// (taking the aggregated status of a directory from the index of the tree)
+    pub(super) fn to_list_source(&self, index: Index, tree: &Tree) -> Option<ListSource> {
+        match *self {
             Self::Feed {
-                title,
-                url,
+                ref title,
+                ref url,
                 updating,
+                ref favicon,
+                retention,
                 parent_index,
+                ..
             } => Some(ListSource::Feed {
-                title,
-                url,
+                title: title.clone(),
+                url: url.clone(),
                 updating,
+                error: self.error_message(),
+                failing: self.is_failing_feed(),
+                favicon: favicon.clone(),
+                retention,
                 parent_index,
                 index,
             }),
             Self::Directory {
-                title,
-                children,
+                ref title,
+                ref children,
                 parent_index,
             } => Some(ListSource::Directory {
-                title,
-                children,
+                title: title.clone(),
+                children: children.clone(),
+                updating: tree.is_updating(index),
+                error: tree.error(index),
+                failing: tree.is_failing(index),
+                parent_index,
+                index,
+            }),
+            Self::Search {
+                ref title,
+                parent_index,
+                ..
+            } => Some(ListSource::Search {
+                title: title.clone(),
                 parent_index,
                 index,
             }),
@@ -236,7 +447,7 @@ impl ArenaSource {
 }
 
 /// List source
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ListSource {
     /// Feed
     Feed {
@@ -246,6 +457,14 @@ pub enum ListSource {
         url: String,
         /// Is the feed in the process of being updated?
         updating: bool,
+        /// Error of the last update (if it failed)
+        error: Option<String>,
+        /// Does the feed keep failing to update?
+        failing: bool,
+        /// Path to the cached favicon (if there is one)
+        favicon: Option<PathBuf>,
+        /// Retention policy of the tidings (the global one, if there is none)
+        retention: Option<Policy>,
         /// Arena index of the parent directory
         parent_index: Index,
         /// Arena index of the source
@@ -257,6 +476,21 @@ pub enum ListSource {
         title: String,
         /// Children arena indices
         children: Vec<Index>,
+        /// Are any of the children in the process of being updated?
+        updating: bool,
+        /// Errors of the last update of the children (if any failed)
+        error: Option<String>,
+        /// Do any of the children keep failing to update?
+        failing: bool,
+        /// Arena index of the parent directory
+        parent_index: Index,
+        /// Arena index of the source
+        index: Index,
+    },
+    /// Saved search
+    Search {
+        /// Title
+        title: String,
         /// Arena index of the parent directory
         parent_index: Index,
         /// Arena index of the source
@@ -268,63 +502,194 @@ impl ListSource {
     /// Get the title of the source
     fn title(&self) -> &str {
         match *self {
-            Self::Feed { ref title, .. } | Self::Directory { ref title, .. } => title,
+            Self::Feed { ref title, .. }
+            | Self::Directory { ref title, .. }
+            | Self::Search { ref title, .. } => title,
+        }
+    }
+    /// Is the source in the process of being updated?
+    fn updating(&self) -> bool {
+        match *self {
+            Self::Feed { updating, .. } | Self::Directory { updating, .. } => updating,
+            Self::Search { .. } => false,
+        }
+    }
+    /// Get the error of the last update
+    fn error(&self) -> Option<&str> {
+        match *self {
+            Self::Feed { ref error, .. } | Self::Directory { ref error, .. } => error.as_deref(),
+            Self::Search { .. } => None,
+        }
+    }
+    /// Does the source keep failing to update?
+    fn failing(&self) -> bool {
+        match *self {
+            Self::Feed { failing, .. } | Self::Directory { failing, .. } => failing,
+            Self::Search { .. } => false,
+        }
+    }
+    /// Get the icon of the source
+    fn icon(&self) -> gio::Icon {
+        match *self {
+            Self::Feed { ref favicon, .. } => favicons::icon(favicon.as_deref()),
+            Self::Directory { .. } => gio::ThemedIcon::new("inode-directory-symbolic").upcast(),
+            Self::Search { .. } => gio::ThemedIcon::new("system-search-symbolic").upcast(),
         }
     }
     /// Get the index of the source
     pub(super) fn index(&self) -> &Index {
         match *self {
-            Self::Feed { ref index, .. } | Self::Directory { ref index, .. } => index,
+            Self::Feed { ref index, .. }
+            | Self::Directory { ref index, .. }
+            | Self::Search { ref index, .. } => index,
         }
     }
-    /// Is the source a directory?
-    pub(super) fn is_dir(&self) -> bool {
-        matches!(self, &Self::Directory { .. })
+    /// Is the source a saved search?
+    fn is_search(&self) -> bool {
+        matches!(self, &Self::Search { .. })
+    }
+    /// Is the source a feed?
+    fn is_feed(&self) -> bool {
+        matches!(self, &Self::Feed { .. })
+    }
+    /// Get the retention policy of the feed (if it has its own)
+    fn retention(&self) -> Option<Policy> {
+        match *self {
+            Self::Feed { retention, .. } => retention,
+            Self::Directory { .. } | Self::Search { .. } => None,
+        }
     }
 }
 
-/// Messages
-#[derive(Debug)]
-pub enum Msg {}
-
-#[allow(clippy::missing_docs_in_private_items)]
-#[relm4::factory(pub)]
-impl FactoryComponent for ListSource {
-    type CommandOutput = ();
-    type Init = ListSource;
-    type Input = Msg;
-    type Output = super::Msg;
-    type ParentMsg = super::Msg;
-    type ParentWidget = gtk::ListBox;
-    type Widgets = Widgets;
-    view! {
+/// Set up the row widget of the sources list
+#[allow(clippy::too_many_lines)]
+pub(super) fn setup(list_item: &gtk::ListItem, sender: &ComponentSender<super::Model>) {
+    // The handlers look up the source the row is currently bound to
+    let item = list_item.downgrade();
+    let sender = sender.clone();
+    relm4::view! {
         // Action Row
-        adw::ActionRow {
-            #[watch]
-            set_title: self.title(),
-            set_activatable: true,
+        row = adw::ActionRow {
+            // Show the context menu on a right click
+            add_controller = &gtk::GestureClick {
+                set_button: gdk::BUTTON_SECONDARY,
+                connect_pressed[popover] => move |_, _, _, _| {
+                    popover.popup();
+                }
+            },
             // Favicon
-            add_prefix = &gtk::Image {
-                set_icon_name: if self.is_dir() {
-                    Some("inode-directory-symbolic")
-                } else {
-                    Some("emblem-shared-symbolic")
-                },
+            add_prefix: icon = &gtk::Image {},
+            // Updating Spinner
+            add_suffix: spinner = &gtk::Spinner {
+                set_tooltip_text: Some("Updating"),
+            },
+            // Error Icon
+            add_suffix: error = &gtk::Image {},
+            // Context Menu Button
+            add_suffix: menu_button = &gtk::MenuButton {
+                set_icon_name: "view-more-symbolic",
+                set_tooltip_text: Some("More"),
+                set_valign: gtk::Align::Center,
+                add_css_class: "flat",
+                // Context Menu
+                #[wrap(Some)]
+                set_popover: popover = &gtk::Popover {
+                    #[wrap(Some)]
+                    set_child = &gtk::Box {
+                        set_orientation: gtk::Orientation::Vertical,
+                        // Update Button
+                        append = &gtk::Button {
+                            set_label: "Update",
+                            add_css_class: "flat",
+                            connect_clicked[sender, popover, item] => move |_| {
+                                popover.popdown();
+                                // Ask the parent to update this source
+                                if let Some(source) = bound(&item) {
+                                    sender.input(super::Msg::StartUpdate(*source.index()));
+                                }
+                            }
+                        },
+                        // Retention Separator
+                        append: separator = &gtk::Separator {},
+                        // Keep as Set Globally Check Button
+                        append: keep_global = &gtk::CheckButton {
+                            set_label: Some("Keep as Set Globally"),
+                        },
+                        // Keep All Check Button
+                        append: keep_all = &gtk::CheckButton {
+                            set_label: Some("Keep All Tidings"),
+                            set_group: Some(&keep_global),
+                        },
+                        // Keep the Last Tidings Check Button
+                        append: keep_last = &gtk::CheckButton {
+                            set_label: Some("Keep the Last 100 Tidings"),
+                            set_group: Some(&keep_global),
+                        },
+                        // Keep for a Month Check Button
+                        append: keep_month = &gtk::CheckButton {
+                            set_label: Some("Keep Tidings for a Month"),
+                            set_group: Some(&keep_global),
+                        },
+                    }
+                }
             },
         }
     }
-    fn init_model(
-        source: Self::Init,
-        _index: &DynamicIndex,
-        _sender: FactoryComponentSender<Self>,
-    ) -> Self {
-        // The callers should construct the variants themselves
-        source
-    }
-    fn update(&mut self, msg: Self::Input, _sender: FactoryComponentSender<Self>) {
-        match msg {}
-    }
-    fn output_to_parent_msg(output: Self::Output) -> Option<super::Msg> {
-        Some(output)
+    // Ask the parent to set the policy of the feed on a toggle of the check buttons
+    let buttons = [
+        (keep_global, None),
+        (keep_all, Some(Policy::Forever)),
+        (keep_last, Some(Policy::Last(100))),
+        (keep_month, Some(Policy::Days(30))),
+    ];
+    for &(ref button, policy) in &buttons {
+        let sender = sender.clone();
+        let item = item.clone();
+        button.connect_toggled(move |button| {
+            // Ignore the toggles caused by binding the row to another source
+            match bound(&item) {
+                Some(source) if button.is_active() && source.retention() != policy => {
+                    sender.input(super::Msg::SetRetention(*source.index(), policy));
+                }
+                Some(_) | None => {}
+            }
+        });
     }
+    list_item.set_child(Some(&row));
+    // Show the source the row is bound to
+    list_item.connect_item_notify(move |list_item| {
+        let source = match list::item::<ListSource>(list_item) {
+            Some(source) => source,
+            None => return,
+        };
+        row.set_title(source.title());
+        icon.set_from_gicon(&source.icon());
+        spinner.set_visible(source.updating());
+        spinner.set_spinning(source.updating());
+        error.set_icon_name(Some(if source.failing() {
+            "dialog-error-symbolic"
+        } else {
+            "dialog-warning-symbolic"
+        }));
+        error.set_css_classes(if source.failing() {
+            &["error"]
+        } else {
+            &["warning"]
+        });
+        error.set_visible(source.error().is_some());
+        error.set_tooltip_text(source.error());
+        // Saved searches can't be updated
+        menu_button.set_visible(!source.is_search());
+        // Only the feeds have their own retention policies
+        separator.set_visible(source.is_feed());
+        for &(ref button, policy) in &buttons {
+            button.set_visible(source.is_feed());
+            button.set_active(source.retention() == policy);
+        }
+    });
+}
+
+/// Get the source the row is currently bound to
+fn bound(item: &glib::WeakRef<gtk::ListItem>) -> Option<ListSource> {
+    item.upgrade().and_then(|list_item| list::item(&list_item))
 }
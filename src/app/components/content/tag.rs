@@ -0,0 +1,56 @@
+//! Tag (in the tag browser)
+
+use adw::prelude::{ActionRowExt, PreferencesRowExt};
+use gtk::prelude::WidgetExt;
+use gtk::traits::ListBoxRowExt;
+use relm4::factory::{DynamicIndex, FactoryComponent, FactoryComponentSender};
+
+/// Model
+#[derive(Debug, Clone)]
+pub struct Model {
+    /// Name
+    pub name: String,
+    /// Number of the tidings tagged with it
+    pub count: usize,
+}
+
+/// Messages
+#[derive(Debug)]
+pub enum Msg {}
+
+#[allow(clippy::missing_docs_in_private_items)]
+#[relm4::factory(pub)]
+impl FactoryComponent for Model {
+    type CommandOutput = ();
+    type Init = Model;
+    type Input = Msg;
+    type Output = ();
+    type ParentMsg = super::Msg;
+    type ParentWidget = gtk::ListBox;
+    type Widgets = Widgets;
+    view! {
+        // Action Row
+        adw::ActionRow {
+            #[watch]
+            set_title: &self.name,
+            set_activatable: true,
+            // Counter
+            add_suffix = &gtk::Label {
+                #[watch]
+                set_label: &self.count.to_string(),
+                add_css_class: "dim-label",
+            },
+        }
+    }
+    fn init_model(
+        tag: Self::Init,
+        _index: &DynamicIndex,
+        _sender: FactoryComponentSender<Self>,
+    ) -> Self {
+        // The callers should construct the tags themselves
+        tag
+    }
+    fn update(&mut self, msg: Self::Input, _sender: FactoryComponentSender<Self>) {
+        match msg {}
+    }
+}
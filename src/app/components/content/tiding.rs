@@ -1,51 +1,266 @@
 //! Tiding
 
 use adw::prelude::{ActionRowExt, PreferencesRowExt};
-use gtk::traits::ListBoxRowExt;
-use relm4::factory::{DynamicIndex, FactoryComponent, FactoryComponentSender};
+use gtk::prelude::{
+    BoxExt, ButtonExt, GestureSingleExt, ObjectExt, OrientableExt, PopoverExt, WidgetExt,
+};
+use gtk::{gdk, gio, glib};
+use relm4::ComponentSender;
+
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use super::{favicons, list};
+
+/// Normalize the tag entered by the user (the tags are in lowercase, so that
+/// the same tag isn't added twice, and can't contain whitespace, so they can
+/// be used in the search queries)
+pub fn normalize_tag(tag: &str) -> String {
+    tag.split_whitespace()
+        .collect::<Vec<_>>()
+        .join("-")
+        .to_lowercase()
+}
 
 /// Model
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Model {
+    /// Identifier (unique within the feed)
+    pub id: String,
     /// Title
     pub title: String,
+    /// Link
+    pub link: Option<String>,
+    /// Summary
+    pub summary: Option<String>,
+    /// Content
+    pub content: Option<String>,
+    /// Names of the authors
+    pub authors: Vec<String>,
+    /// Categories
+    pub categories: Vec<String>,
+    /// Time of the publication (or of the last update, if unknown)
+    pub published: Option<SystemTime>,
+    /// Time the tiding was fetched for the first time
+    pub fetched: SystemTime,
+    /// URL of the feed this tiding came from
+    pub feed_url: String,
+    /// Has the tiding been read?
+    pub read: bool,
+    /// Is the tiding starred?
+    pub starred: bool,
+    /// IDs of the filter rules that hide the tiding
+    pub hidden_by: Vec<u64>,
+    /// Tags
+    pub tags: Vec<String>,
+    /// Path to the cached favicon of the feed (if there is one)
+    pub favicon: Option<PathBuf>,
+    /// Title of the section of the list the tiding starts (if it's the first one of the day)
+    pub section: Option<String>,
 }
 
-/// Messages
-#[derive(Debug)]
-pub enum Msg {}
-
-#[allow(clippy::missing_docs_in_private_items)]
-#[relm4::factory(pub)]
-impl FactoryComponent for Model {
-    type CommandOutput = ();
-    type Init = Model;
-    type Input = Msg;
-    type Output = ();
-    type ParentMsg = super::Msg;
-    type ParentWidget = gtk::ListBox;
-    type Widgets = Widgets;
-    view! {
-        // Action Row
-        adw::ActionRow {
-            #[watch]
-            set_title: &self.title,
-            set_activatable: true,
-            // Favicon
-            add_prefix = &gtk::Image {
-                set_icon_name: Some("emblem-shared-symbolic")
-            },
-        }
+impl Model {
+    /// Get the time the tiding is sorted by (the time of its publication, or of its fetch)
+    pub(super) fn time(&self) -> SystemTime {
+        self.published.unwrap_or(self.fetched)
     }
-    fn init_model(
-        tiding: Self::Init,
-        _index: &DynamicIndex,
-        _sender: FactoryComponentSender<Self>,
-    ) -> Self {
-        // The callers should construct the variants themselves
-        tiding
+    /// Create a tiding from the feed entry
+    pub(super) fn from_entry(entry: feed_rs::model::Entry, feed_url: &str) -> Self {
+        Self {
+            title: entry.title.map(|title| title.content).unwrap_or_default(),
+            link: entry.links.into_iter().next().map(|link| link.href),
+            summary: entry.summary.map(|summary| summary.content),
+            content: entry.content.and_then(|content| content.body),
+            authors: entry
+                .authors
+                .into_iter()
+                .map(|author| author.name)
+                .collect(),
+            categories: entry
+                .categories
+                .into_iter()
+                .map(|category| category.label.unwrap_or(category.term))
+                .collect(),
+            published: entry.published.or(entry.updated).map(SystemTime::from),
+            fetched: SystemTime::now(),
+            id: entry.id,
+            feed_url: feed_url.to_owned(),
+            read: false,
+            starred: false,
+            hidden_by: Vec::new(),
+            tags: Vec::new(),
+            favicon: None,
+            section: None,
+        }
     }
-    fn update(&mut self, msg: Self::Input, _sender: FactoryComponentSender<Self>) {
-        match msg {}
+}
+
+/// Set up the row widget of the tidings list
+#[allow(clippy::too_many_lines)]
+pub(super) fn setup(list_item: &gtk::ListItem, sender: &ComponentSender<super::Model>) {
+    // The handlers look up the tiding the row is currently bound to
+    let item = list_item.downgrade();
+    let sender = sender.clone();
+    relm4::view! {
+        // Box
+        row = gtk::Box {
+            set_orientation: gtk::Orientation::Vertical,
+            // Section Header
+            append: header = &gtk::Label {
+                set_halign: gtk::Align::Start,
+                set_margin_top: 12,
+                set_margin_bottom: 6,
+                set_margin_start: 12,
+                add_css_class: "heading",
+            },
+            // Action Row
+            append: action_row = &adw::ActionRow {
+                // Show the context menu on a right click
+                add_controller = &gtk::GestureClick {
+                    set_button: gdk::BUTTON_SECONDARY,
+                    connect_pressed[popover] => move |_, _, _, _| {
+                        popover.popup();
+                    }
+                },
+                // Favicon
+                add_prefix: favicon = &gtk::Image {},
+                // Unread Indicator
+                add_suffix: unread = &gtk::Image {
+                    set_icon_name: Some("media-record-symbolic"),
+                    set_tooltip_text: Some("Unread"),
+                },
+                // Starred Indicator
+                add_suffix: starred = &gtk::Image {
+                    set_icon_name: Some("starred-symbolic"),
+                    set_tooltip_text: Some("Starred"),
+                },
+                // Context Menu Button
+                add_suffix = &gtk::MenuButton {
+                    set_icon_name: "view-more-symbolic",
+                    set_tooltip_text: Some("More"),
+                    set_valign: gtk::Align::Center,
+                    add_css_class: "flat",
+                    // Context Menu
+                    #[wrap(Some)]
+                    set_popover: popover = &gtk::Popover {
+                        #[wrap(Some)]
+                        set_child = &gtk::Box {
+                            set_orientation: gtk::Orientation::Vertical,
+                            // Open in Browser Button
+                            append: open_button = &gtk::Button {
+                                set_label: "Open in Browser",
+                                add_css_class: "flat",
+                                connect_clicked[popover, item] => move |_| {
+                                    popover.popdown();
+                                    // If there is a link, open it with the default application
+                                    if let Some(link) = bound(&item).and_then(|tiding| tiding.link) {
+                                        gio::AppInfo::launch_default_for_uri(
+                                            &link,
+                                            None::<&gio::AppLaunchContext>,
+                                        )
+                                        .ok();
+                                    }
+                                }
+                            },
+                            // Copy Link Button
+                            append: copy_button = &gtk::Button {
+                                set_label: "Copy Link",
+                                add_css_class: "flat",
+                                connect_clicked[popover, item] => move |button| {
+                                    popover.popdown();
+                                    // Put the link into the clipboard
+                                    if let Some(link) = bound(&item).and_then(|tiding| tiding.link) {
+                                        button.clipboard().set_text(&link);
+                                    }
+                                }
+                            },
+                            // Toggle Read Button
+                            append: read_button = &gtk::Button {
+                                add_css_class: "flat",
+                                connect_clicked[sender, popover, item] => move |_| {
+                                    popover.popdown();
+                                    // Save the toggled status in the dictionary
+                                    if let Some(tiding) = bound(&item) {
+                                        sender.input(super::Msg::SetRead(
+                                            tiding.feed_url,
+                                            tiding.id,
+                                            !tiding.read,
+                                        ));
+                                    }
+                                }
+                            },
+                            // Toggle Starred Button
+                            append: star_button = &gtk::Button {
+                                add_css_class: "flat",
+                                connect_clicked[sender, popover, item] => move |_| {
+                                    popover.popdown();
+                                    // Save the toggled status in the dictionary
+                                    if let Some(tiding) = bound(&item) {
+                                        sender.input(super::Msg::SetStarred(
+                                            tiding.feed_url,
+                                            tiding.id,
+                                            !tiding.starred,
+                                        ));
+                                    }
+                                }
+                            },
+                            // Edit Tags Button
+                            append = &gtk::Button {
+                                set_label: "Edit Tags",
+                                add_css_class: "flat",
+                                connect_clicked[sender, popover, item] => move |_| {
+                                    popover.popdown();
+                                    // Ask the parent to open the tags dialog
+                                    if let Some(tiding) = bound(&item) {
+                                        sender.input(super::Msg::EditTags(tiding.feed_url, tiding.id));
+                                    }
+                                }
+                            },
+                            // Go to Feed Button
+                            append = &gtk::Button {
+                                set_label: "Go to Feed",
+                                add_css_class: "flat",
+                                connect_clicked[sender, popover, item] => move |_| {
+                                    popover.popdown();
+                                    // Ask the parent to show the feed
+                                    if let Some(tiding) = bound(&item) {
+                                        sender.input(super::Msg::GoToFeed(tiding.feed_url));
+                                    }
+                                }
+                            },
+                        }
+                    }
+                },
+            },
+        }
     }
+    list_item.set_child(Some(&row));
+    // Show the tiding the row is bound to
+    list_item.connect_item_notify(move |list_item| {
+        let tiding = match list::item::<Model>(list_item) {
+            Some(tiding) => tiding,
+            None => return,
+        };
+        // Show the header only if the tiding starts a section
+        header.set_visible(tiding.section.is_some());
+        header.set_label(tiding.section.as_deref().unwrap_or_default());
+        action_row.set_title(&tiding.title);
+        action_row.set_subtitle(&tiding.tags.join(", "));
+        favicon.set_from_gicon(&favicons::icon(tiding.favicon.as_deref()));
+        unread.set_visible(!tiding.read);
+        starred.set_visible(tiding.starred);
+        open_button.set_sensitive(tiding.link.is_some());
+        copy_button.set_sensitive(tiding.link.is_some());
+        read_button.set_label(if tiding.read {
+            "Mark as Unread"
+        } else {
+            "Mark as Read"
+        });
+        star_button.set_label(if tiding.starred { "Unstar" } else { "Star" });
+    });
+}
+
+/// Get the tiding the row is currently bound to
+fn bound(item: &glib::WeakRef<gtk::ListItem>) -> Option<Model> {
+    item.upgrade().and_then(|list_item| list::item(&list_item))
 }
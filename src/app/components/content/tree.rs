@@ -0,0 +1,266 @@
+//! Index of the tree of the sources (kept up to date on every change, so
+//! that the lookups don't have to walk the tree)
+
+use generational_arena::Index;
+use wyhash::WyHash;
+
+use std::collections::HashMap;
+use std::hash::BuildHasherDefault;
+use std::iter;
+
+use super::source::{ArenaSource, URLsMap};
+
+/// A type alias to the dictionary of the (Index, T) key-value pairs
+type IndexMap<T> = HashMap<Index, T, BuildHasherDefault<WyHash>>;
+
+/// Index of the tree of the sources
+#[derive(Debug, Default)]
+pub(super) struct Tree {
+    /// Arena indices of the feeds with each URL
+    nodes: URLsMap,
+    /// Feeds under each source (counting recursively down, including
+    /// the source itself if it's a feed), by their URLs
+    feeds: IndexMap<URLsMap>,
+    /// Ancestors of each source (from the parent up to the root)
+    ancestors: IndexMap<Vec<Index>>,
+    /// Update status of the feeds under each source (counting
+    /// recursively down, including the source itself if it's a feed)
+    statuses: IndexMap<Status>,
+}
+
+/// Aggregated update status of the feeds under a source
+#[derive(Debug, Default)]
+struct Status {
+    /// Number of the feeds in the process of being updated
+    updating: usize,
+    /// Number of the feeds that keep failing to update
+    failing: usize,
+    /// Errors of the last update of the feeds (sorted by the indices of the feeds)
+    errors: Vec<(Index, String)>,
+}
+
+impl Tree {
+    /// Create an empty index
+    pub(super) fn new() -> Self {
+        Self::default()
+    }
+    /// Add the source (its parent is expected to be added already)
+    pub(super) fn insert(&mut self, index: Index, source: &ArenaSource) {
+        // Inherit the ancestors of the parent
+        let ancestors = match source.parent_index() {
+            Some(&parent_index) => {
+                let mut ancestors = vec![parent_index];
+                if let Some(parent_ancestors) = self.ancestors.get(&parent_index) {
+                    ancestors.extend_from_slice(parent_ancestors);
+                }
+                ancestors
+            }
+            None => Vec::new(),
+        };
+        self.feeds.insert(index, URLsMap::default());
+        self.statuses.insert(index, Status::default());
+        // If it's a feed, add it under its own URL to itself and each of the ancestors
+        if let Some(url) = source.url() {
+            self.add_feed(index, &ancestors, url);
+        }
+        self.ancestors.insert(index, ancestors);
+        // Count its status in, too
+        self.set_status(index, source);
+    }
+    /// Update the status of the feed in its own status and the aggregated ones of its ancestors
+    pub(super) fn set_status(&mut self, index: Index, source: &ArenaSource) {
+        // Only the feeds have the status of their own
+        if source.url().is_none() {
+            return;
+        }
+        // Get the previous status of the feed
+        let (was_updating, was_failing) =
+            self.statuses.get(&index).map_or((false, false), |status| {
+                (status.updating > 0, status.failing > 0)
+            });
+        let updating = source.is_updating_feed();
+        let failing = source.is_failing_feed();
+        let error = source.error_message();
+        // Apply the changes to the feed and each of the ancestors
+        let ancestors = self.ancestors.get(&index).map_or(&[][..], Vec::as_slice);
+        for node in iter::once(&index).chain(ancestors) {
+            if let Some(status) = self.statuses.get_mut(node) {
+                status.updating = shift(status.updating, was_updating, updating);
+                status.failing = shift(status.failing, was_failing, failing);
+                // Replace the error of the feed, keeping the errors sorted
+                status.errors.retain(|&(feed, _)| feed != index);
+                if let Some(ref error) = error {
+                    let position = status.errors.partition_point(|&(feed, _)| feed < index);
+                    status.errors.insert(position, (index, error.clone()));
+                }
+            }
+        }
+    }
+    /// Move the feed to the new URL
+    pub(super) fn set_url(&mut self, index: Index, old_url: &str, new_url: &str) {
+        let ancestors = self.ancestors.get(&index).cloned().unwrap_or_default();
+        // Remove the feed from under the old URL
+        for node in iter::once(&index).chain(&ancestors) {
+            if let Some(feeds) = self.feeds.get_mut(node) {
+                remove_from(feeds, old_url, index);
+            }
+        }
+        remove_from(&mut self.nodes, old_url, index);
+        // Add it under the new one
+        self.add_feed(index, &ancestors, new_url);
+    }
+    /// Add the feed under the URL to itself and each of the ancestors
+    fn add_feed(&mut self, index: Index, ancestors: &[Index], url: &str) {
+        for node in iter::once(&index).chain(ancestors) {
+            if let Some(feeds) = self.feeds.get_mut(node) {
+                feeds.entry(url.to_owned()).or_default().push(index);
+            }
+        }
+        self.nodes.entry(url.to_owned()).or_default().push(index);
+    }
+    /// Get the arena indices of the feeds with this URL
+    pub(super) fn nodes(&self, url: &str) -> &[Index] {
+        self.nodes.get(url).map_or(&[], Vec::as_slice)
+    }
+    /// Get the dictionary of the (URL, Vec<Index>) pairs of the feeds under the source
+    pub(super) fn feeds(&self, index: Index) -> Option<&URLsMap> {
+        self.feeds.get(&index)
+    }
+    /// Get the URLs of the feeds under the source
+    pub(super) fn urls(&self, index: Index) -> impl Iterator<Item = &String> {
+        self.feeds.get(&index).into_iter().flat_map(URLsMap::keys)
+    }
+    /// Is any of the feeds under the source in the process of being updated?
+    pub(super) fn is_updating(&self, index: Index) -> bool {
+        self.statuses
+            .get(&index)
+            .map_or(false, |status| status.updating > 0)
+    }
+    /// Does any of the feeds under the source keep failing to update?
+    pub(super) fn is_failing(&self, index: Index) -> bool {
+        self.statuses
+            .get(&index)
+            .map_or(false, |status| status.failing > 0)
+    }
+    /// Get the errors of the last update of the feeds under the source (one per line)
+    pub(super) fn error(&self, index: Index) -> Option<String> {
+        self.statuses
+            .get(&index)
+            .filter(|status| !status.errors.is_empty())
+            .map(|status| {
+                status
+                    .errors
+                    .iter()
+                    .map(|&(_, ref error)| error.as_str())
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+    }
+    /// Is the source a child (not necessarily a direct one) of the specified source?
+    pub(super) fn is_child_of(&self, index: Index, ancestor: Index) -> bool {
+        self.ancestors
+            .get(&index)
+            .map_or(false, |ancestors| ancestors.contains(&ancestor))
+    }
+}
+
+/// Remove the feed from under the URL (and the URL, if no feeds are left under it)
+fn remove_from(feeds: &mut URLsMap, url: &str, index: Index) {
+    if let Some(indices) = feeds.get_mut(url) {
+        indices.retain(|other| *other != index);
+        if indices.is_empty() {
+            feeds.remove(url);
+        }
+    }
+}
+
+/// Adjust the number of the feeds with some status after one of them has changed it
+fn shift(count: usize, was: bool, is: bool) -> usize {
+    match (was, is) {
+        (false, true) => count + 1,
+        (true, false) => count.saturating_sub(1),
+        (false, false) | (true, true) => count,
+    }
+}
+
+/// Tests of the index of the tree
+#[cfg(test)]
+mod tests {
+    use generational_arena::Arena;
+
+    use super::super::update::Error as UpdateError;
+    use super::*;
+
+    /// Update the source in the arena and its status in the tree
+    fn update(
+        arena: &mut Arena<ArenaSource>,
+        tree: &mut Tree,
+        index: Index,
+        change: impl FnOnce(&mut ArenaSource),
+    ) {
+        if let Some(source) = arena.get_mut(index) {
+            change(source);
+            tree.set_status(index, source);
+        }
+    }
+
+    /// The statuses of the feeds are aggregated up the tree and follow the feeds to the new URLs
+    #[test]
+    fn statuses() {
+        let mut arena = Arena::new();
+        let mut tree = Tree::new();
+        let root = arena.insert(ArenaSource::new_root());
+        let directory = arena.insert(ArenaSource::new_directory(String::from("D"), root));
+        let a = arena.insert(ArenaSource::new_feed(
+            String::from("A"),
+            String::from("https://a.example.com/feed"),
+            directory,
+        ));
+        let b = arena.insert(ArenaSource::new_feed(
+            String::from("B"),
+            String::from("https://b.example.com/feed"),
+            root,
+        ));
+        for (index, source) in arena.iter() {
+            tree.insert(index, source);
+        }
+        assert!(!tree.is_updating(root));
+        // The updates are counted up the ancestors
+        update(&mut arena, &mut tree, a, |source| source.set_updating(true));
+        update(&mut arena, &mut tree, b, |source| source.set_updating(true));
+        assert!(tree.is_updating(root) && tree.is_updating(directory));
+        update(&mut arena, &mut tree, b, |source| {
+            source.set_updating(false);
+        });
+        assert!(tree.is_updating(root) && !tree.is_updating(b));
+        // The status stays after moving the feed to the new URL
+        let new_url = "https://a.example.org/feed";
+        tree.set_url(a, "https://a.example.com/feed", new_url);
+        update(&mut arena, &mut tree, a, |source| {
+            source.set_url(new_url.to_owned());
+        });
+        assert_eq!(tree.nodes(new_url), &[a]);
+        assert!(tree.nodes("https://a.example.com/feed").is_empty());
+        assert!(tree.urls(root).any(|url| url == new_url));
+        assert!(tree.is_updating(root) && tree.is_updating(directory));
+        update(&mut arena, &mut tree, a, |source| {
+            source.set_updating(false);
+        });
+        assert!(!tree.is_updating(root) && !tree.is_updating(directory));
+        // The errors are kept in the order of the feeds
+        update(&mut arena, &mut tree, b, |source| {
+            source.set_error(Some(UpdateError::Gone));
+        });
+        update(&mut arena, &mut tree, a, |source| {
+            source.set_error(Some(UpdateError::Timeout));
+        });
+        let errors = format!("A: {}\nB: {}", UpdateError::Timeout, UpdateError::Gone);
+        assert_eq!(tree.error(root), Some(errors));
+        assert!(tree.is_failing(root) && !tree.is_failing(directory) && !tree.is_failing(a));
+        // The recovered feeds are no longer counted
+        update(&mut arena, &mut tree, b, |source| source.set_error(None));
+        assert!(!tree.is_failing(root));
+        assert_eq!(tree.error(directory), tree.error(a));
+        assert_eq!(tree.error(b), None);
+    }
+}
@@ -1,60 +1,507 @@
 //! Update message handler
 
-use rayon::prelude::*;
+mod hints;
+
+use generational_arena::Index;
+use gtk::gio;
+use gtk::prelude::SettingsExt;
 use relm4::{Component, ComponentSender, Worker, WorkerController};
+use reqwest::header::{
+    HeaderMap, HeaderName, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, LOCATION,
+};
+use reqwest::StatusCode;
+use tokio::sync::{watch, Semaphore};
+use wyhash::WyHash;
 
+use std::collections::HashMap;
 use std::convert::identity;
+use std::fmt;
+use std::hash::BuildHasherDefault;
+use std::mem;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use super::{favicons, Tiding};
+pub use hints::Hints;
+
+/// Maximum number of redirects to follow
+const REDIRECTS: usize = 10;
+/// Maximum number of retries of a transient failure
+const ATTEMPTS: u32 = 3;
+/// Delay before the first retry
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+/// Delay before the next update of a feed that failed once
+const BACKOFF: Duration = Duration::from_secs(15 * 60);
+/// Maximum delay before the next update of a failing feed
+const MAX_BACKOFF: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Get the delay before the next retry (exponential, with jitter)
+fn retry_delay(attempts: u32) -> Duration {
+    RETRY_DELAY * 2_u32.pow(attempts.saturating_sub(1))
+        + Duration::from_millis(fastrand::u64(0..1000))
+}
+
+/// Get the delay before the next update of a feed
+/// that failed this many times in a row
+pub(super) fn backoff(failures: u32) -> Duration {
+    (BACKOFF * 2_u32.pow(failures.saturating_sub(1).min(7))).min(MAX_BACKOFF)
+}
+
+/// Limits of the requests
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    /// Maximum number of requests in flight
+    global: usize,
+    /// Maximum number of requests in flight to the same host
+    per_host: usize,
+    /// Timeout of a single request
+    timeout: Duration,
+}
+
+impl From<&gio::Settings> for Limits {
+    fn from(settings: &gio::Settings) -> Self {
+        Self {
+            global: usize::try_from(settings.uint("update-concurrency"))
+                .unwrap_or(1)
+                .max(1),
+            per_host: usize::try_from(settings.uint("update-host-concurrency"))
+                .unwrap_or(1)
+                .max(1),
+            timeout: Duration::from_secs(settings.uint("update-timeout").into()),
+        }
+    }
+}
+
+/// Cache validators of a feed
+#[derive(Debug, Clone, Default)]
+pub struct Validators {
+    /// Value of the `ETag` header
+    etag: Option<String>,
+    /// Value of the `Last-Modified` header
+    last_modified: Option<String>,
+}
+
+impl From<&HeaderMap> for Validators {
+    fn from(headers: &HeaderMap) -> Self {
+        // Get the value of the header as a string
+        let get = |name: HeaderName| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_owned)
+        };
+        Self {
+            etag: get(ETAG),
+            last_modified: get(LAST_MODIFIED),
+        }
+    }
+}
+
+/// Request to update a feed
+#[derive(Debug)]
+pub struct Request {
+    /// URL of the feed
+    pub(super) url: String,
+    /// Arena indices of the feeds with this URL
+    pub(super) indices: Vec<Index>,
+    /// Cache validators of the last successful update
+    pub(super) validators: Validators,
+    /// Update hints of the last successful update
+    pub(super) hints: Hints,
+}
 
-use super::{Tiding, URLsMap};
+/// Result of a successful update
+#[derive(Debug)]
+pub struct Updated {
+    /// Tidings of the feed
+    pub(super) tidings: Vec<Tiding>,
+    /// New cache validators
+    pub(super) validators: Validators,
+    /// New update hints
+    pub(super) hints: Hints,
+    /// Time of the next allowed update
+    pub(super) next_update: SystemTime,
+    /// Candidate URLs of the favicon
+    pub(super) icons: Vec<String>,
+}
+
+/// Result of a successful fetch
+enum Fetched {
+    /// The feed hasn't changed since the last update (with the time of the next allowed update)
+    NotModified(SystemTime),
+    /// The feed has been updated
+    Updated(Updated),
+}
 
 /// Model
-pub struct Model;
+pub struct Model {
+    /// ID of the message handler
+    id: usize,
+    /// Limits of the requests
+    limits: Limits,
+    /// Sender of the cancellation signal
+    cancel: watch::Sender<bool>,
+}
+
+impl Drop for Model {
+    fn drop(&mut self) {
+        // Abort the requests in flight
+        self.cancel.send(true).ok();
+    }
+}
 
-/// Initialize a new worker (this is
-/// used to drop the previous worker)
-pub(super) fn new(sender: &ComponentSender<super::Model>) -> WorkerController<Model> {
+/// Initialize a new worker with this ID and limits
+/// (dropping the worker cancels its ongoing update)
+pub(super) fn new(
+    sender: &ComponentSender<super::Model>,
+    id: usize,
+    limits: Limits,
+) -> WorkerController<Model> {
     Model::builder()
-        .detach_worker(())
+        .detach_worker((id, limits))
         .forward(&sender.input, identity)
 }
 
+/// Reasons of a failed update
+#[derive(Debug, Clone)]
+pub enum Error {
+    /// The server responded with an unsuccessful status code
+    /// (and, possibly, the time before which it shouldn't be retried)
+    Status(StatusCode, Option<SystemTime>),
+    /// The feed is gone for good
+    Gone,
+    /// The request timed out
+    Timeout,
+    /// Couldn't connect to the server
+    Connect(String),
+    /// The request couldn't be completed
+    Request(String),
+    /// The response couldn't be parsed as a feed
+    Parse(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Status(ref status, _) => write!(f, "HTTP status {}", status),
+            Self::Gone => write!(f, "The feed is gone for good"),
+            Self::Timeout => write!(f, "The request timed out"),
+            Self::Connect(ref message) => write!(f, "Couldn't connect: {}", message),
+            Self::Request(ref message) => write!(f, "Request failed: {}", message),
+            Self::Parse(ref message) => write!(f, "Couldn't parse the feed: {}", message),
+        }
+    }
+}
+
+impl Error {
+    /// Get the time before which the feed shouldn't be fetched again (if known)
+    pub(super) fn not_before(&self) -> Option<SystemTime> {
+        match *self {
+            Self::Status(_, not_before) => not_before,
+            Self::Gone | Self::Timeout | Self::Connect(_) | Self::Request(_) | Self::Parse(_) => {
+                None
+            }
+        }
+    }
+    /// Check whether the failure is likely to go away on a retry
+    fn is_transient(&self) -> bool {
+        match *self {
+            Self::Status(ref status, _) => status.is_server_error(),
+            Self::Timeout | Self::Connect(_) => true,
+            Self::Gone | Self::Request(_) | Self::Parse(_) => false,
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(error: reqwest::Error) -> Self {
+        if error.is_timeout() {
+            Self::Timeout
+        } else if error.is_connect() {
+            Self::Connect(error.to_string())
+        } else {
+            Self::Request(error.to_string())
+        }
+    }
+}
+
+/// Get the host of the URL (or an empty string, if there is none)
+fn host(url: &str) -> String {
+    reqwest::Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_owned))
+        .unwrap_or_default()
+}
+
+/// Fetch the feed at this URL and parse its tidings. Redirects are followed manually,
+/// so the new URL is returned if the feed has been moved permanently
+async fn fetch(
+    client: &reqwest::Client,
+    url: &str,
+    validators: &Validators,
+    hints: &Hints,
+) -> Result<(Option<String>, Fetched), Error> {
+    let mut current = url.to_owned();
+    let mut moved = None;
+    let mut permanent = true;
+    let mut redirects = 0;
+    let response = loop {
+        // Prepare a conditional request
+        let mut request = client.get(&current);
+        if let Some(ref etag) = validators.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(ref last_modified) = validators.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+        // Send the request
+        let response = request.send().await?;
+        // Stop if it's not a redirect
+        let status = response.status();
+        if !status.is_redirection() || status == StatusCode::NOT_MODIFIED {
+            break response;
+        }
+        // Don't get stuck in a loop
+        if redirects >= REDIRECTS {
+            return Err(Error::Request(String::from("Too many redirects")));
+        }
+        redirects += 1;
+        // Resolve the target of the redirect
+        let location = response
+            .headers()
+            .get(LOCATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|location| response.url().join(location).ok())
+            .ok_or_else(|| Error::Request(String::from("Redirect without a location")))?;
+        // Only a chain of permanent redirects moves the feed
+        permanent &=
+            status == StatusCode::MOVED_PERMANENTLY || status == StatusCode::PERMANENT_REDIRECT;
+        if permanent {
+            moved = Some(location.to_string());
+        }
+        current = location.into();
+    };
+    let now = SystemTime::now();
+    // Check if the feed has changed at all
+    let status = response.status();
+    if status == StatusCode::NOT_MODIFIED {
+        let next_update = hints.next_update(response.headers(), now);
+        return Ok((moved, Fetched::NotModified(next_update)));
+    }
+    // Check if the feed is still there
+    if status == StatusCode::GONE {
+        return Err(Error::Gone);
+    }
+    // Make sure the request was successful
+    if !status.is_success() {
+        return Err(Error::Status(
+            status,
+            hints::not_before(response.headers(), now),
+        ));
+    }
+    // Get the new cache validators
+    let headers = response.headers().clone();
+    let validators = Validators::from(&headers);
+    // Get the body of the response
+    let bytes = response.bytes().await?;
+    // Parse the feed
+    let feed =
+        feed_rs::parser::parse(bytes.as_ref()).map_err(|error| Error::Parse(error.to_string()))?;
+    // Get the new update hints
+    let hints = Hints::parse(bytes.as_ref());
+    let next_update = hints.next_update(&headers, now);
+    // Convert the entries to tidings (under the new URL, if the feed has moved)
+    let feed_url = moved.as_deref().unwrap_or(url);
+    let icons = favicons::candidates(&feed, feed_url);
+    let tidings = feed
+        .entries
+        .into_iter()
+        .map(|entry| Tiding::from_entry(entry, feed_url))
+        .collect();
+    Ok((
+        moved,
+        Fetched::Updated(Updated {
+            tidings,
+            validators,
+            hints,
+            next_update,
+            icons,
+        }),
+    ))
+}
+
+/// Update the feed, respecting the concurrency limits (the
+/// updating status is tagged with the ID of the message handler)
+async fn update_feed(
+    id: usize,
+    client: reqwest::Client,
+    favicon_client: reqwest::Client,
+    global: Arc<Semaphore>,
+    host: Arc<Semaphore>,
+    mut cancelled: watch::Receiver<bool>,
+    request: Request,
+) {
+    let Request {
+        url,
+        indices,
+        validators,
+        hints,
+    } = request;
+    // Skip the feed if the update has been cancelled
+    if *cancelled.borrow() {
+        return;
+    }
+    // Fetch the feed, aborting the request on cancellation
+    let result = tokio::select! {
+        result = async {
+            let mut attempts = 0;
+            loop {
+                let result = {
+                    // Wait for a free slot for the host first, and then for a global one
+                    let _host_permit = host.acquire().await;
+                    let _global_permit = global.acquire().await;
+                    // Add the updating status on the first attempt
+                    if attempts == 0 {
+                        super::BROKER.send(super::Msg::UpdateStarted(id, indices.clone()));
+                    }
+                    fetch(&client, &url, &validators, &hints).await
+                };
+                // Give up if the failure isn't transient or there are no attempts left
+                let retry = matches!(result, Err(ref error) if error.is_transient());
+                if !retry || attempts >= ATTEMPTS {
+                    break result;
+                }
+                // Otherwise, wait a bit (without holding the slots) and retry
+                attempts += 1;
+                tokio::time::sleep(retry_delay(attempts)).await;
+            }
+        } => Some(result),
+        _ = cancelled.changed() => None,
+    };
+    match result {
+        // The update has been cancelled, so
+        // the state is reset by the receiver
+        None => {}
+        Some(Ok((moved, fetched))) => {
+            // If the feed has moved permanently, migrate it to the new URL first
+            let url = if let Some(new_url) = moved {
+                super::BROKER.send(super::Msg::Moved(indices.clone(), url, new_url.clone()));
+                new_url
+            } else {
+                url
+            };
+            match fetched {
+                Fetched::NotModified(next_update) => {
+                    // Count the request as handled
+                    super::BROKER.send(super::Msg::NotModified(indices, url, next_update));
+                }
+                Fetched::Updated(mut updated) => {
+                    // Keep the candidate URLs of the favicon for later
+                    let icons = mem::take(&mut updated.icons);
+                    // Insert the tidings into the dictionary
+                    super::BROKER.send(super::Msg::Insert(indices.clone(), url.clone(), updated));
+                    // Fetch the favicon if it's missing or outdated
+                    if favicons::is_stale(&url) {
+                        tokio::select! {
+                            fetched = async {
+                                // Respect the concurrency limits, too
+                                let _host_permit = host.acquire().await;
+                                let _global_permit = global.acquire().await;
+                                favicons::fetch(&favicon_client, &url, icons).await
+                            } => {
+                                if let Some(path) = fetched {
+                                    super::BROKER.send(super::Msg::FaviconFetched(indices, path));
+                                }
+                            },
+                            _ = cancelled.changed() => {},
+                        }
+                    }
+                }
+            }
+        }
+        Some(Err(error)) => {
+            // Report the failure
+            super::BROKER.send(super::Msg::UpdateFailed(indices, url, error));
+        }
+    }
+}
+
 /// Messages
 #[derive(Debug)]
 pub enum Msg {
-    /// Update all feeds
-    UpdateAll(URLsMap),
+    /// Update all feeds with these requests (in the order of priority)
+    UpdateAll(Vec<Request>),
 }
 
 impl Worker for Model {
-    type Init = ();
+    type Init = (usize, Limits);
     type Input = Msg;
     type Output = super::Msg;
-    fn init(_init: Self::Init, _sender: ComponentSender<Self>) -> Self {
-        Self
+    fn init((id, limits): Self::Init, _sender: ComponentSender<Self>) -> Self {
+        let (cancel, _) = watch::channel(false);
+        Self { id, limits, cancel }
     }
     fn update(&mut self, msg: Msg, sender: ComponentSender<Self>) {
         match msg {
-            Msg::UpdateAll(indices_urls) => {
+            Msg::UpdateAll(requests) => {
+                // Get the ID of the message handler
+                let id = self.id;
+                // Get the limits of the requests
+                let limits = self.limits;
+                // Subscribe to the cancellation signal
+                let cancelled = self.cancel.subscribe();
                 // Add a new command future to be executed in the background
-                sender.command(|_out, shutdown| {
+                sender.command(move |_out, shutdown| {
                     // Cancel the future if the component is shut down in the meantime
                     shutdown
                         .register(async move {
-                            // For each pair
-                            indices_urls.into_par_iter().for_each(|(url, indices)| {
-                                // Add the updating status
-                                super::BROKER.send(super::Msg::UpdateStarted(indices.clone()));
-                                // Prepare some fake results
-                                let tidings = vec![Tiding {
-                                    title: format!("URL: {}", url),
-                                }];
-                                // Imitate some work
-                                std::thread::sleep(std::time::Duration::from_secs(1));
-                                // Insert the tidings into the dictionary
-                                super::BROKER.send(super::Msg::Insert(indices, url, tidings));
-                            });
-                            // Notify Feeds that the whole update is finished
-                            super::BROKER.send(super::Msg::StopUpdateAll);
+                            // Prepare a client for the requests of the feeds (the
+                            // redirects are followed manually to notice the permanent ones)
+                            let client = reqwest::Client::builder()
+                                .connect_timeout(limits.timeout)
+                                .timeout(limits.timeout)
+                                .redirect(reqwest::redirect::Policy::none())
+                                .build()
+                                .unwrap_or_else(|_| reqwest::Client::new());
+                            // Prepare a client for the requests of the
+                            // favicons (following the redirects)
+                            let favicon_client = reqwest::Client::builder()
+                                .connect_timeout(limits.timeout)
+                                .timeout(limits.timeout)
+                                .build()
+                                .unwrap_or_else(|_| reqwest::Client::new());
+                            // Prepare the semaphores (they're fair, so
+                            // the order of the requests is preserved)
+                            let global = Arc::new(Semaphore::new(limits.global));
+                            let mut hosts: HashMap<
+                                String,
+                                Arc<Semaphore>,
+                                BuildHasherDefault<WyHash>,
+                            > = HashMap::default();
+                            // Spawn a task for each request
+                            let mut tasks = Vec::with_capacity(requests.len());
+                            for request in requests {
+                                // Get the semaphore of the host
+                                let host = hosts
+                                    .entry(host(&request.url))
+                                    .or_insert_with(|| Arc::new(Semaphore::new(limits.per_host)))
+                                    .clone();
+                                tasks.push(tokio::spawn(update_feed(
+                                    id,
+                                    client.clone(),
+                                    favicon_client.clone(),
+                                    global.clone(),
+                                    host,
+                                    cancelled.clone(),
+                                    request,
+                                )));
+                            }
+                            // Wait for all of them to finish
+                            for task in tasks {
+                                task.await.ok();
+                            }
+                            // Notify Feeds that this update is finished
+                            super::BROKER.send(super::Msg::UpdateFinished(id));
                         })
                         .drop_on_shutdown()
                 });
@@ -0,0 +1,312 @@
+//! Update hints
+
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use reqwest::header::{HeaderMap, HeaderName, CACHE_CONTROL, EXPIRES, RETRY_AFTER};
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Number of seconds in an hour
+const HOUR: u64 = 60 * 60;
+
+/// Number of seconds in a day
+const DAY: u64 = 24 * HOUR;
+
+/// Maximum delay the feed or the server can ask for
+const MAX_DELAY: Duration = Duration::from_secs(365 * DAY);
+
+/// Update hints provided by the feed itself
+#[derive(Debug, Clone, Default)]
+pub struct Hints {
+    /// How long the feed can be cached (from `<ttl>` or `sy:updatePeriod`/`sy:updateFrequency`)
+    ttl: Option<Duration>,
+    /// Hours (in UTC) during which the feed shouldn't be fetched
+    skip_hours: Vec<u64>,
+    /// Days of the week (starting with Sunday as 0) during which the feed shouldn't be fetched
+    skip_days: Vec<u64>,
+}
+
+impl Hints {
+    /// Parse the hints from the body of the feed
+    pub(super) fn parse(bytes: &[u8]) -> Self {
+        let mut hints = Self::default();
+        // Period and frequency of the syndication module
+        let mut period = None;
+        let mut frequency = 1;
+        // Prepare a reader
+        let mut reader = Reader::from_reader(bytes);
+        reader.trim_text(true);
+        let mut buf = Vec::new();
+        // Names of the currently open elements
+        let mut path: Vec<Vec<u8>> = Vec::new();
+        loop {
+            match reader.read_event(&mut buf) {
+                Ok(Event::Start(ref element)) => {
+                    let name = element.local_name();
+                    // The hints are on the channel level, so stop at the first item
+                    if name == b"item" || name == b"entry" {
+                        break;
+                    }
+                    path.push(name.to_vec());
+                }
+                Ok(Event::End(_)) => {
+                    path.pop();
+                }
+                Ok(Event::Text(ref text)) => {
+                    let text = text.unescape_and_decode(&reader).unwrap_or_default();
+                    let parent = path.len().checked_sub(2).and_then(|i| path.get(i));
+                    match path.last().map(Vec::as_slice) {
+                        Some(b"ttl") => {
+                            hints.ttl = text
+                                .parse::<u64>()
+                                .ok()
+                                .map(|minutes| Duration::from_secs(minutes.saturating_mul(60)));
+                        }
+                        Some(b"hour") if parent.map(Vec::as_slice) == Some(&b"skipHours"[..]) => {
+                            // Drop the invalid hours
+                            if let Some(hour) = text.parse::<u64>().ok().filter(|hour| *hour < 24) {
+                                hints.skip_hours.push(hour);
+                            }
+                        }
+                        Some(b"day") if parent.map(Vec::as_slice) == Some(&b"skipDays"[..]) => {
+                            if let Some(day) = weekday(&text) {
+                                hints.skip_days.push(day);
+                            }
+                        }
+                        Some(b"updatePeriod") => {
+                            period = match text.as_str() {
+                                "hourly" => Some(HOUR),
+                                "daily" => Some(DAY),
+                                "weekly" => Some(7 * DAY),
+                                "monthly" => Some(30 * DAY),
+                                "yearly" => Some(365 * DAY),
+                                _ => None,
+                            };
+                        }
+                        Some(b"updateFrequency") => {
+                            frequency = text.parse::<u64>().unwrap_or(1).max(1);
+                        }
+                        Some(_) | None => {}
+                    }
+                }
+                Ok(
+                    Event::Empty(_)
+                    | Event::CData(_)
+                    | Event::Comment(_)
+                    | Event::Decl(_)
+                    | Event::PI(_)
+                    | Event::DocType(_),
+                ) => {}
+                Ok(Event::Eof) | Err(_) => break,
+            }
+            buf.clear();
+        }
+        // Prefer the explicit time to live (the frequency is at least 1)
+        if hints.ttl.is_none() {
+            #[allow(clippy::integer_division)]
+            let ttl = period.map(|period| Duration::from_secs(period / frequency));
+            hints.ttl = ttl;
+        }
+        hints
+    }
+    /// Compute the time of the next allowed update from
+    /// the hints and the headers of the last response
+    pub(super) fn next_update(&self, headers: &HeaderMap, now: SystemTime) -> SystemTime {
+        // Start with the time to live of the feed
+        let mut next = self.ttl.map_or(now, |ttl| after(now, ttl));
+        // Respect the caching headers
+        if let Some(not_before) = not_before(headers, now) {
+            next = next.max(not_before);
+        }
+        // Move forward hour by hour while in the skipped hours or days
+        if !self.skip_hours.is_empty() || !self.skip_days.is_empty() {
+            for _ in 0_u32..7 * 24 {
+                let secs = next
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |duration| duration.as_secs());
+                // The UNIX epoch is on Thursday
+                #[allow(clippy::integer_division)]
+                let (hour, day) = ((secs % DAY) / HOUR, (secs / DAY + 4) % 7);
+                if !self.skip_hours.contains(&hour) && !self.skip_days.contains(&day) {
+                    break;
+                }
+                // Go to the start of the next hour
+                next = UNIX_EPOCH + Duration::from_secs(secs - secs % HOUR + HOUR);
+            }
+        }
+        next
+    }
+}
+
+/// Get the time before which the feed shouldn't be fetched from the
+/// `Cache-Control`, `Expires` and `Retry-After` headers of the response
+pub(super) fn not_before(headers: &HeaderMap, now: SystemTime) -> Option<SystemTime> {
+    // Get the value of the header as a string
+    let get = |name: HeaderName| headers.get(name).and_then(|value| value.to_str().ok());
+    // Take the `max-age` directive of the `Cache-Control` header,
+    // falling back to the `Expires` header
+    let expires = get(CACHE_CONTROL)
+        .and_then(|value| {
+            value.split(',').find_map(|directive| {
+                directive
+                    .trim()
+                    .strip_prefix("max-age=")
+                    .and_then(|seconds| seconds.parse::<u64>().ok())
+            })
+        })
+        .map(|seconds| after(now, Duration::from_secs(seconds)))
+        .or_else(|| get(EXPIRES).and_then(|value| httpdate::parse_http_date(value).ok()));
+    // The `Retry-After` header is either a number of seconds or a date
+    let retry_after = get(RETRY_AFTER).and_then(|value| {
+        value
+            .parse::<u64>()
+            .ok()
+            .map(|seconds| after(now, Duration::from_secs(seconds)))
+            .or_else(|| httpdate::parse_http_date(value).ok())
+    });
+    expires.max(retry_after)
+}
+
+/// Get the time after the delay (clamped to a sane maximum, so
+/// that the absurd delays sent by the servers can't overflow)
+fn after(now: SystemTime, delay: Duration) -> SystemTime {
+    now.checked_add(delay.min(MAX_DELAY)).unwrap_or(now)
+}
+
+/// Get the number of the day of the week (starting with Sunday as 0)
+fn weekday(name: &str) -> Option<u64> {
+    match name.trim().to_lowercase().as_str() {
+        "sunday" => Some(0),
+        "monday" => Some(1),
+        "tuesday" => Some(2),
+        "wednesday" => Some(3),
+        "thursday" => Some(4),
+        "friday" => Some(5),
+        "saturday" => Some(6),
+        _ => None,
+    }
+}
+
+/// Tests of the update hints
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    /// Number of seconds in half an hour
+    const HALF_HOUR: u64 = 30 * 60;
+
+    /// Get the time this number of seconds after the UNIX epoch (Thursday, 00:00)
+    fn at(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    /// The time to live is in minutes
+    #[test]
+    fn ttl() {
+        let hints = Hints::parse(b"<rss><channel><ttl>90</ttl></channel></rss>");
+        assert_eq!(hints.ttl, Some(Duration::from_secs(90 * 60)));
+    }
+
+    /// The period of the syndication module is split by the frequency
+    #[test]
+    fn syndication_period() {
+        let hints = Hints::parse(
+            b"<rss xmlns:sy=\"http://purl.org/rss/1.0/modules/syndication/\"><channel>\
+            <sy:updatePeriod>daily</sy:updatePeriod>\
+            <sy:updateFrequency>4</sy:updateFrequency>\
+            </channel></rss>",
+        );
+        assert_eq!(hints.ttl, Some(Duration::from_secs(6 * HOUR)));
+    }
+
+    /// Only the valid skipped hours and days on the channel level are taken
+    #[test]
+    fn skip_hours_and_days() {
+        let hints = Hints::parse(
+            b"<rss><channel>\
+            <skipHours><hour>10</hour><hour>35</hour></skipHours>\
+            <skipDays><day>Friday</day><day>Someday</day></skipDays>\
+            <item><skipHours><hour>1</hour></skipHours></item>\
+            </channel></rss>",
+        );
+        assert_eq!(hints.skip_hours, vec![10]);
+        assert_eq!(hints.skip_days, vec![5]);
+    }
+
+    /// The next update is after the time to live
+    #[test]
+    fn next_update_ttl() {
+        let hints = Hints::parse(b"<rss><channel><ttl>60</ttl></channel></rss>");
+        assert_eq!(
+            hints.next_update(&HeaderMap::new(), at(HALF_HOUR)),
+            at(HOUR + HALF_HOUR)
+        );
+    }
+
+    /// The skipped hours are jumped over
+    #[test]
+    fn next_update_skip_hours() {
+        let hints = Hints::parse(
+            b"<rss><channel><skipHours><hour>10</hour><hour>11</hour></skipHours></channel></rss>",
+        );
+        // Thursday, 10:30 is moved to 12:00
+        assert_eq!(
+            hints.next_update(&HeaderMap::new(), at(10 * HOUR + HALF_HOUR)),
+            at(12 * HOUR)
+        );
+        // Thursday, 9:30 stays as it is
+        assert_eq!(
+            hints.next_update(&HeaderMap::new(), at(9 * HOUR + HALF_HOUR)),
+            at(9 * HOUR + HALF_HOUR)
+        );
+    }
+
+    /// The skipped days are jumped over
+    #[test]
+    fn next_update_skip_days() {
+        let hints = Hints::parse(
+            b"<rss><channel><skipDays><day>Thursday</day><day>Friday</day></skipDays></channel></rss>",
+        );
+        // Thursday, 10:30 is moved to Saturday, 00:00
+        assert_eq!(
+            hints.next_update(&HeaderMap::new(), at(10 * HOUR + HALF_HOUR)),
+            at(2 * DAY)
+        );
+    }
+
+    /// The caching headers can only postpone the next update
+    #[test]
+    fn next_update_headers() {
+        let hints = Hints::parse(b"<rss><channel><ttl>1</ttl></channel></rss>");
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            CACHE_CONTROL,
+            HeaderValue::from_static("public, max-age=600"),
+        );
+        assert_eq!(hints.next_update(&headers, at(0)), at(600));
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("1200"));
+        assert_eq!(hints.next_update(&headers, at(0)), at(1200));
+        headers.insert(CACHE_CONTROL, HeaderValue::from_static("max-age=30"));
+        headers.remove(RETRY_AFTER);
+        assert_eq!(hints.next_update(&headers, at(0)), at(60));
+    }
+
+    /// The absurd delays are clamped instead of overflowing the time
+    #[test]
+    fn next_update_huge_delays() {
+        let hints = Hints::parse(b"<rss><channel><ttl>18446744073709551615</ttl></channel></rss>");
+        assert_eq!(hints.next_update(&HeaderMap::new(), at(0)), at(365 * DAY));
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            CACHE_CONTROL,
+            HeaderValue::from_static("max-age=18446744073709551615"),
+        );
+        assert_eq!(not_before(&headers, at(0)), Some(at(365 * DAY)));
+        headers.insert(
+            RETRY_AFTER,
+            HeaderValue::from_static("18446744073709551615"),
+        );
+        assert_eq!(not_before(&headers, at(0)), Some(at(365 * DAY)));
+    }
+}
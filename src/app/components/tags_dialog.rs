@@ -0,0 +1,238 @@
+//! Tags Dialog
+
+mod tag;
+
+use adw::prelude::{ActionRowExt, PreferencesRowExt};
+use gtk::prelude::{
+    BoxExt, ButtonExt, EditableExt, EntryBufferExtManual, EntryExt, GtkListStoreExtManual,
+    GtkWindowExt, OrientableExt, StaticType, WidgetExt,
+};
+use relm4::factory::{DynamicIndex, FactoryVecDeque};
+use relm4::{ComponentParts, ComponentSender, MessageBroker, SimpleComponent};
+
+use super::content::{self, tiding::normalize_tag};
+use super::AppMsg;
+use tag::Model as Tag;
+
+/// Message broker
+pub static BROKER: MessageBroker<Model> = MessageBroker::new();
+
+/// Model
+pub struct Model {
+    /// Is the window visible?
+    visible: bool,
+    /// URL of the feed of the tiding
+    url: String,
+    /// Identifier of the tiding
+    id: String,
+    /// Tag entry buffer
+    tag: gtk::EntryBuffer,
+    /// Tags of the tiding
+    tags: FactoryVecDeque<Tag>,
+    /// Known tags to complete the entry with
+    completions: gtk::ListStore,
+    /// Is the tag allowed to be added?
+    allowed: bool,
+}
+
+impl Model {
+    /// Get the names of the tags of the tiding
+    fn names(&self) -> Vec<String> {
+        (0..self.tags.len())
+            .filter_map(|index| self.tags.get(index))
+            .map(|tag| tag.name.clone())
+            .collect()
+    }
+}
+
+/// Messages
+#[derive(Debug)]
+pub enum Msg {
+    /// Show the dialog for the tiding (Feed URL, ID, tags, known tags)
+    Show(String, String, Vec<String>, Vec<String>),
+    /// Hide the dialog
+    Hide,
+    /// Check if the tag is allowed to be added
+    Check,
+    /// Add the tag
+    Add,
+    /// Remove the tag with this index
+    Remove(DynamicIndex),
+}
+
+/// Get a clone of the Tags List Box
+fn tags_list_box(model: &Model) -> gtk::ListBox {
+    model.tags.widget().clone()
+}
+
+#[allow(clippy::clone_on_ref_ptr)]
+#[allow(clippy::missing_docs_in_private_items)]
+#[allow(unused_variables)]
+#[relm4::component(pub)]
+impl SimpleComponent for Model {
+    type Init = ();
+    type Input = Msg;
+    type Output = AppMsg;
+    type Widgets = Widgets;
+    fn init(
+        _init: Self::Init,
+        root: &Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        // Initialize the model
+        let model = Self {
+            visible: false,
+            url: String::from(""),
+            id: String::from(""),
+            tag: gtk::EntryBuffer::default(),
+            tags: FactoryVecDeque::new(gtk::ListBox::new(), &sender.input),
+            completions: gtk::ListStore::new(&[String::static_type()]),
+            allowed: false,
+        };
+        let widgets = view_output!();
+        ComponentParts { model, widgets }
+    }
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            Msg::Show(url, id, tags, known) => {
+                // Remember the tiding
+                self.url = url;
+                self.id = id;
+                // Offer the known tags the tiding doesn't have yet
+                self.completions.clear();
+                for name in known.iter().filter(|name| !tags.contains(name)) {
+                    self.completions
+                        .set(&self.completions.append(), &[(0, name)]);
+                }
+                // Show the tags of the tiding
+                let mut tags_guard = self.tags.guard();
+                tags_guard.clear();
+                for name in tags {
+                    tags_guard.push_back(name);
+                }
+                self.visible = true;
+            }
+            Msg::Hide => {
+                // Hide the widget
+                self.visible = false;
+                // Empty the buffer
+                self.tag.delete_text(0, None);
+            }
+            Msg::Check => {
+                // Don't allow the empty tags and the duplicates
+                let name = normalize_tag(&self.tag.text());
+                self.allowed = !name.is_empty() && !self.names().contains(&name);
+            }
+            Msg::Add => {
+                // Get the tag
+                let name = normalize_tag(&self.tag.text());
+                if !name.is_empty() && !self.names().contains(&name) {
+                    // Show it in the list
+                    self.tags.guard().push_back(name);
+                    // Empty the buffer
+                    self.tag.delete_text(0, None);
+                    // Save the tags
+                    content::BROKER.send(content::Msg::SetTags(
+                        self.url.clone(),
+                        self.id.clone(),
+                        self.names(),
+                    ));
+                }
+            }
+            Msg::Remove(index) => {
+                // Remove the tag from the list
+                self.tags.guard().remove(index.current_index());
+                // Save the tags
+                content::BROKER.send(content::Msg::SetTags(
+                    self.url.clone(),
+                    self.id.clone(),
+                    self.names(),
+                ));
+            }
+        }
+    }
+    fn pre_view() {
+        // Focus on the tag entry when opening the dialog
+        if !tags_dialog.is_visible() {
+            tag_entry.grab_focus();
+        }
+    }
+    view! {
+        tags_dialog = gtk::Dialog {
+            set_title: Some("Edit Tags"),
+            set_width_request: 313,
+            set_modal: true,
+            set_vexpand: false,
+            #[watch]
+            set_visible: model.visible,
+            set_default_widget: Some(&add_button),
+            connect_close_request[sender] => move |_| {
+                sender.input(Msg::Hide);
+                gtk::Inhibit(false)
+            },
+            // Clamp
+            #[wrap(Some)]
+            set_child = &adw::Clamp {
+                set_maximum_size: 400,
+                // Box
+                #[wrap(Some)]
+                set_child = &gtk::Box {
+                    set_orientation: gtk::Orientation::Vertical,
+                    set_margin_top: 24,
+                    set_margin_bottom: 24,
+                    set_margin_start: 12,
+                    set_margin_end: 12,
+                    set_spacing: 24,
+                    // Boxed List
+                    append = &gtk::ListBox {
+                        set_selection_mode: gtk::SelectionMode::None,
+                        add_css_class: "boxed-list",
+                        // Action Row
+                        append = &adw::ActionRow {
+                            set_title: "Tag",
+                            // Tag entry
+                            add_suffix: tag_entry = &gtk::Entry {
+                                set_margin_top: 7,
+                                set_margin_bottom: 7,
+                                set_buffer: &model.tag,
+                                set_activates_default: true,
+                                // Complete the known tags
+                                #[wrap(Some)]
+                                set_completion = &gtk::EntryCompletion {
+                                    set_model: Some(&model.completions),
+                                    set_text_column: 0,
+                                    set_minimum_key_length: 1,
+                                    set_inline_completion: true,
+                                },
+                                // Check if adding the tag is allowed on an entry change
+                                connect_changed[sender] => move |_| {
+                                    sender.input(Msg::Check);
+                                }
+                            },
+                            // Add Button
+                            add_suffix: add_button = &gtk::Button {
+                                set_margin_top: 7,
+                                set_margin_bottom: 7,
+                                set_css_classes: &["suggested-action", "circular"],
+                                set_icon_name: "plus-large-symbolic",
+                                #[watch]
+                                set_sensitive: model.allowed,
+                                // Add on the press of the button
+                                connect_clicked[sender] => move |_| {
+                                    sender.input(Msg::Add);
+                                }
+                            },
+                        }
+                    },
+                    // Tags Boxed List
+                    append = &tags_list_box(&model) -> gtk::ListBox {
+                        #[watch]
+                        set_visible: !model.tags.is_empty(),
+                        set_selection_mode: gtk::SelectionMode::None,
+                        add_css_class: "boxed-list",
+                    },
+                }
+            },
+        }
+    }
+}